@@ -0,0 +1,107 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::{Graph, SpfOpt, spf};
+
+/// Costs and ECMP nexthops between every pair of nodes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DistanceMatrix {
+    /// Node ids in row and column order.
+    pub ids: Vec<usize>,
+    // Row major, one row per source.
+    costs: Vec<Option<u32>>,
+    nexthops: Vec<BTreeSet<usize>>,
+}
+
+impl DistanceMatrix {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    fn index(&self, from: usize, to: usize) -> Option<usize> {
+        let row = self.ids.binary_search(&from).ok()?;
+        let col = self.ids.binary_search(&to).ok()?;
+        Some(row * self.ids.len() + col)
+    }
+
+    /// Cost from `from` to `to`, `None` when unreachable or unknown.
+    pub fn cost(&self, from: usize, to: usize) -> Option<u32> {
+        self.index(from, to).and_then(|index| self.costs[index])
+    }
+
+    /// First hops from `from` toward `to`, empty when `to` is `from` itself
+    /// or unreachable.
+    pub fn nexthops(&self, from: usize, to: usize) -> Option<&BTreeSet<usize>> {
+        self.index(from, to).map(|index| &self.nexthops[index])
+    }
+}
+
+/// Runs SPF from every node of `graph` on `threads` threads, all of them
+/// sharing the graph. With `threads` 0 the available parallelism is used.
+/// The result does not depend on the number of threads.
+pub fn all_pairs_spf(graph: &Graph, threads: usize) -> DistanceMatrix {
+    let ids = graph.keys().copied().collect::<Vec<_>>();
+    let n = ids.len();
+
+    let threads = if threads == 0 {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        threads
+    };
+
+    // Each thread takes the next root until none is left, rows are stored
+    // by root index.
+    let next = AtomicUsize::new(0);
+    let rows = Mutex::new(vec![None; n]);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(n) {
+            scope.spawn(|| {
+                let opt = SpfOpt::default();
+                loop {
+                    let row = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(root) = ids.get(row) else {
+                        break;
+                    };
+                    let spf = spf(graph, *root, &opt);
+                    let mut costs = Vec::with_capacity(n);
+                    let mut nexthops = Vec::with_capacity(n);
+                    for id in &ids {
+                        let path = spf.get(id);
+                        costs.push(path.map(|path| path.cost));
+                        nexthops.push(
+                            path.iter()
+                                .flat_map(|path| path.nexthops.iter())
+                                .filter_map(|nhop| nhop.get(1).copied())
+                                .collect::<BTreeSet<_>>(),
+                        );
+                    }
+                    // A poisoned lock means another thread panicked, which
+                    // the scope rethrows anyway.
+                    rows.lock().expect("matrix rows lock poisoned")[row] = Some((costs, nexthops));
+                }
+            });
+        }
+    });
+
+    let mut matrix = DistanceMatrix {
+        ids,
+        costs: Vec::with_capacity(n * n),
+        nexthops: Vec::with_capacity(n * n),
+    };
+    let rows = rows.into_inner().expect("matrix rows lock poisoned");
+    for (row, result) in rows.into_iter().enumerate() {
+        // Every root is taken by a thread before they all stop.
+        let (costs, nexthops) =
+            result.unwrap_or_else(|| panic!("no SPF result for node {}", matrix.ids[row]));
+        matrix.costs.extend(costs);
+        matrix.nexthops.extend(nexthops);
+    }
+    matrix
+}
@@ -0,0 +1,76 @@
+use crate::{Graph, Link, Node};
+
+/// Builds a `Graph` while keeping the `olinks` of each link's source and
+/// the `ilinks` of its destination in sync.
+#[derive(Debug, Default, Clone)]
+pub struct GraphBuilder {
+    graph: Graph,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `node` with empty adjacency lists. Returns false when a node
+    /// with the same id already exists.
+    pub fn add_node(&mut self, mut node: Node) -> bool {
+        if self.graph.contains_key(&node.id) {
+            return false;
+        }
+        node.olinks.clear();
+        node.ilinks.clear();
+        self.graph.insert(node.id, node);
+        true
+    }
+
+    /// Adds the directed `link`. Returns false when one of its endpoints is
+    /// not in the graph.
+    pub fn add_link(&mut self, link: Link) -> bool {
+        if !self.graph.contains_key(&link.from) || !self.graph.contains_key(&link.to) {
+            return false;
+        }
+        if let Some(node) = self.graph.get_mut(&link.to) {
+            node.ilinks.push(link.clone());
+        }
+        if let Some(node) = self.graph.get_mut(&link.from) {
+            node.olinks.push(link);
+        }
+        true
+    }
+
+    /// Adds `link` and the link in the opposite direction with the same
+    /// attributes.
+    pub fn add_bidir_link(&mut self, link: Link) -> bool {
+        let mut reverse = link.clone();
+        reverse.from = link.to;
+        reverse.to = link.from;
+
+        self.add_link(link) && self.add_link(reverse)
+    }
+
+    /// Removes every copy of the directed `link`. Returns false when the
+    /// graph has no such link.
+    pub fn remove_link(&mut self, link: &Link) -> bool {
+        let mut found = false;
+        if let Some(node) = self.graph.get_mut(&link.from) {
+            let len = node.olinks.len();
+            node.olinks.retain(|l| !l.is_same(link));
+            found |= node.olinks.len() != len;
+        }
+        if let Some(node) = self.graph.get_mut(&link.to) {
+            let len = node.ilinks.len();
+            node.ilinks.retain(|l| !l.is_same(link));
+            found |= node.ilinks.len() != len;
+        }
+        found
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn build(self) -> Graph {
+        self.graph
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::lfa::neighbors;
+use crate::{
+    Failure, Graph, Protection, SpfDag, SpfDirect, SpfError, SrSegment, TilfaOpt, TilfaSource,
+    node_name, q_space_nodes, spf_dag,
+};
+
+/// How TI-LFA protects one (S, X, D) tuple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CoverageKind {
+    /// No segment, the repair path is a plain LFA.
+    Lfa,
+    /// A single Node-SID.
+    NodeSid,
+    /// A Node-SID followed by an Adj-SID.
+    NodeAdj,
+    /// Any other repair list, in practice longer ones.
+    Longer,
+    /// No usable repair path.
+    Unprotected,
+}
+
+impl CoverageKind {
+    /// Kind of `repair_list` computed at S. A leading Adj-SID of S only
+    /// selects the outgoing interface and is not counted.
+    pub fn new(s: usize, repair_list: &[SrSegment]) -> Self {
+        let list = match repair_list.first() {
+            Some(SrSegment::AdjSid(from, _)) if *from == s => &repair_list[1..],
+            _ => repair_list,
+        };
+        match list {
+            [] => CoverageKind::Lfa,
+            [SrSegment::NodeSid(_)] => CoverageKind::NodeSid,
+            [SrSegment::NodeSid(_), SrSegment::AdjSid(..)] => CoverageKind::NodeAdj,
+            _ => CoverageKind::Longer,
+        }
+    }
+}
+
+/// Number of (X, D) tuples of one node, or of the whole network, by kind
+/// of protection.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Coverage {
+    pub lfa: usize,
+    pub node_sid: usize,
+    pub node_adj: usize,
+    pub longer: usize,
+    pub unprotected: usize,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, kind: CoverageKind) {
+        match kind {
+            CoverageKind::Lfa => self.lfa += 1,
+            CoverageKind::NodeSid => self.node_sid += 1,
+            CoverageKind::NodeAdj => self.node_adj += 1,
+            CoverageKind::Longer => self.longer += 1,
+            CoverageKind::Unprotected => self.unprotected += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        self.lfa += other.lfa;
+        self.node_sid += other.node_sid;
+        self.node_adj += other.node_adj;
+        self.longer += other.longer;
+        self.unprotected += other.unprotected;
+    }
+
+    pub fn protected(&self) -> usize {
+        self.lfa + self.node_sid + self.node_adj + self.longer
+    }
+
+    pub fn total(&self) -> usize {
+        self.protected() + self.unprotected
+    }
+
+    /// Protected share of the tuples in percent, 100 when there is none.
+    pub fn ratio(&self) -> f64 {
+        if self.total() == 0 {
+            100.0
+        } else {
+            self.protected() as f64 * 100.0 / self.total() as f64
+        }
+    }
+}
+
+/// TI-LFA coverage of every node, keyed by node id.
+pub type CoverageReport = BTreeMap<usize, Coverage>;
+
+/// Runs `tilfa()` for every node S, every neighbor X of S and every
+/// destination D reachable from S. A tuple counts with the shallowest
+/// repair list among its post-convergence paths, and as unprotected when
+/// `tilfa()` fails. The P space and post-convergence DAG of S are computed
+/// once per (S, X) and the Q space of D once per (D, failure).
+pub fn tilfa_coverage(graph: &Graph, opt: &TilfaOpt) -> CoverageReport {
+    let mut report = CoverageReport::new();
+
+    // Nodes S protecting each neighbor X.
+    let mut sources = BTreeMap::<usize, Vec<usize>>::new();
+    for (s, node) in graph {
+        if node.is_disabled {
+            continue;
+        }
+        report.insert(*s, Coverage::new());
+        for x in neighbors(graph, *s).keys() {
+            sources.entry(*x).or_default().push(*s);
+        }
+    }
+
+    for (x, sources) in &sources {
+        let mut sources = sources
+            .iter()
+            .map(|s| CoverageSource::new(graph, *s, *x, opt))
+            .collect::<Vec<_>>();
+        let node_failure = Failure::node(*x);
+
+        for d in graph.keys() {
+            // The failure of X is the same for every S.
+            let mut node_q_nodes = None;
+
+            for source in sources.iter_mut() {
+                if source.s == *d || !source.dag.nodes.contains_key(d) {
+                    continue;
+                }
+                let mut result = Err(SpfError::NoRepairPath(*d));
+                if d != x
+                    && let Some(node) = source.node.as_mut()
+                {
+                    let q_nodes =
+                        node_q_nodes.get_or_insert_with(|| q_space_nodes(graph, *d, &node_failure));
+                    result = node.repair(graph, *d, q_nodes, opt);
+                }
+                if result.as_ref().is_err_and(|err| err.is_unprotectable()) {
+                    let q_nodes = q_space_nodes(graph, *d, &source.link.failure);
+                    result = source.link.repair(graph, *d, &q_nodes, opt);
+                }
+
+                let kind = match result {
+                    Ok(paths) => paths
+                        .iter()
+                        .map(|path| CoverageKind::new(source.s, &path.repair_list))
+                        .min()
+                        .unwrap_or(CoverageKind::Unprotected),
+                    Err(_) => CoverageKind::Unprotected,
+                };
+                if let Some(coverage) = report.get_mut(&source.s) {
+                    coverage.add(kind);
+                }
+            }
+        }
+    }
+    report
+}
+
+// One S protecting X, shared by every D.
+struct CoverageSource {
+    s: usize,
+    // Pre-convergence SPF DAG of S.
+    dag: SpfDag,
+    // Node protection, tried first as `tilfa()` does.
+    node: Option<TilfaSource>,
+    link: TilfaSource,
+}
+
+impl CoverageSource {
+    fn new(graph: &Graph, s: usize, x: usize, opt: &TilfaOpt) -> Self {
+        let dag = spf_dag(graph, s, &SpfDirect::Normal);
+        let node = (opt.protection == Protection::Node)
+            .then(|| TilfaSource::new(graph, &dag, Failure::node(x), opt));
+        let failure = if opt.protection == Protection::Srlg {
+            Failure::srlg(graph, s, x)
+        } else {
+            Failure::link(graph, s, x)
+        };
+        let link = TilfaSource::new(graph, &dag, failure, opt);
+        Self { s, dag, node, link }
+    }
+}
+
+/// Sum of the coverage of every node.
+pub fn coverage_total(report: &CoverageReport) -> Coverage {
+    let mut total = Coverage::new();
+    for coverage in report.values() {
+        total.merge(coverage);
+    }
+    total
+}
+
+/// Coverage report as CSV, one line per node after the header.
+pub fn coverage_csv(graph: &Graph, report: &CoverageReport) -> String {
+    let mut csv = String::from("node,lfa,node_sid,node_adj,longer,unprotected,total\n");
+    for (id, c) in report {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            node_name(graph, *id),
+            c.lfa,
+            c.node_sid,
+            c.node_adj,
+            c.longer,
+            c.unprotected,
+            c.total()
+        );
+    }
+    csv
+}
+
+fn coverage_line(name: &str, c: &Coverage) {
+    println!(
+        "{:<12} {:>6} {:>6} {:>9} {:>7} {:>12} {:>6} {:>8.1}%",
+        name,
+        c.lfa,
+        c.node_sid,
+        c.node_adj,
+        c.longer,
+        c.unprotected,
+        c.total(),
+        c.ratio()
+    );
+}
+
+pub fn coverage_print(graph: &Graph, report: &CoverageReport) {
+    println!(
+        "{:<12} {:>6} {:>6} {:>9} {:>7} {:>12} {:>6} {:>9}",
+        "Node", "LFA", "Node", "Node+Adj", "Longer", "Unprotected", "Total", "Coverage"
+    );
+    for (id, coverage) in report {
+        coverage_line(&node_name(graph, *id), coverage);
+    }
+    coverage_line("Total", &coverage_total(report));
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::net::Ipv6Addr;
+
+use crate::{Failure, Graph, Link, SpfDirect, Topology};
+
+/// Frozen compressed sparse row copy of a `Graph`. Nodes get dense indices
+/// in ascending id order and the links of each direction are stored as
+/// target indices and costs in contiguous arrays, sliced per node by an
+/// offset array. Node names are dropped, and so are links toward nodes
+/// which are not in the graph.
+#[derive(Debug, Default, Clone)]
+pub struct CsrGraph {
+    // Node id of each index.
+    ids: Vec<usize>,
+    // Whether ids are exactly 0..n, so that an id is its own index.
+    dense: bool,
+    disabled: Bitset,
+    out_edges: CsrEdges,
+    in_edges: CsrEdges,
+    // Distinct attributes of the links, only read to evaluate a `Failure`.
+    attrs: Vec<LinkAttrs>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct CsrEdges {
+    offsets: Vec<usize>,
+    targets: Vec<u32>,
+    costs: Vec<u32>,
+    // Edges whose link is down or disabled.
+    down: Bitset,
+    // Position of the attributes of each edge's link in `CsrGraph::attrs`.
+    attrs: Vec<u32>,
+}
+
+// What tells parallel links apart besides their ends and cost, as compared
+// by `Link::is_same()`. Links with the same attributes share an entry.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct LinkAttrs {
+    srlgs: Vec<u32>,
+    adj_sid: Option<u32>,
+    end_x_sid: Option<Ipv6Addr>,
+    bandwidth: Option<u64>,
+}
+
+impl LinkAttrs {
+    fn new(link: &Link) -> Self {
+        LinkAttrs {
+            srlgs: link.srlgs.clone(),
+            adj_sid: link.adj_sid,
+            end_x_sid: link.end_x_sid,
+            bandwidth: link.bandwidth,
+        }
+    }
+
+    fn is_of(&self, link: &Link) -> bool {
+        self.srlgs == link.srlgs
+            && self.adj_sid == link.adj_sid
+            && self.end_x_sid == link.end_x_sid
+            && self.bandwidth == link.bandwidth
+    }
+}
+
+impl CsrEdges {
+    fn new(len: usize) -> Self {
+        let mut edges = CsrEdges {
+            offsets: Vec::with_capacity(len + 1),
+            ..Default::default()
+        };
+        edges.offsets.push(0);
+        edges
+    }
+
+    fn push(&mut self, target: u32, link: &Link, attrs: u32) {
+        if !link.is_active() {
+            self.down.set(self.targets.len());
+        }
+        self.targets.push(target);
+        self.costs.push(link.cost);
+        self.attrs.push(attrs);
+    }
+
+    fn range(&self, index: usize) -> std::ops::Range<usize> {
+        match (self.offsets.get(index), self.offsets.get(index + 1)) {
+            (Some(start), Some(end)) => *start..*end,
+            _ => 0..0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Bitset(Vec<u64>);
+
+impl Bitset {
+    fn set(&mut self, bit: usize) {
+        let word = bit / 64;
+        if word >= self.0.len() {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << (bit % 64);
+    }
+
+    fn get(&self, bit: usize) -> bool {
+        self.0
+            .get(bit / 64)
+            .is_some_and(|word| word & (1 << (bit % 64)) != 0)
+    }
+}
+
+impl CsrGraph {
+    /// Panics when `graph` has more than `u32::MAX` nodes.
+    pub fn new(graph: &Graph) -> Self {
+        let ids = graph.keys().copied().collect::<Vec<_>>();
+        let dense = ids.iter().enumerate().all(|(index, id)| index == *id);
+        assert!(
+            u32::try_from(ids.len()).is_ok(),
+            "too many nodes for a CSR graph"
+        );
+
+        let mut csr = CsrGraph {
+            ids,
+            dense,
+            disabled: Bitset::default(),
+            out_edges: CsrEdges::new(graph.len()),
+            in_edges: CsrEdges::new(graph.len()),
+            attrs: Vec::new(),
+        };
+
+        let mut table = Vec::new();
+        let mut positions = BTreeMap::<LinkAttrs, u32>::new();
+        let mut attrs = |link: &Link| {
+            let attrs = LinkAttrs::new(link);
+            *positions.entry(attrs.clone()).or_insert_with(|| {
+                table.push(attrs);
+                table.len() as u32 - 1
+            })
+        };
+        for (index, node) in graph.values().enumerate() {
+            if node.is_disabled {
+                csr.disabled.set(index);
+            }
+            for link in &node.olinks {
+                if let Some(target) = csr.index(link.to) {
+                    csr.out_edges.push(target as u32, link, attrs(link));
+                }
+            }
+            csr.out_edges.offsets.push(csr.out_edges.targets.len());
+            for link in &node.ilinks {
+                if let Some(target) = csr.index(link.from) {
+                    csr.in_edges.push(target as u32, link, attrs(link));
+                }
+            }
+            csr.in_edges.offsets.push(csr.in_edges.targets.len());
+        }
+        csr.attrs = table;
+        csr
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Dense index of node `id`.
+    pub fn index(&self, id: usize) -> Option<usize> {
+        if self.dense {
+            (id < self.ids.len()).then_some(id)
+        } else {
+            self.ids.binary_search(&id).ok()
+        }
+    }
+
+    /// Node id of dense index `index`.
+    pub fn id(&self, index: usize) -> Option<usize> {
+        self.ids.get(index).copied()
+    }
+
+    fn edges(&self, direct: &SpfDirect) -> &CsrEdges {
+        match direct {
+            SpfDirect::Normal => &self.out_edges,
+            SpfDirect::Reverse => &self.in_edges,
+        }
+    }
+
+    // Whether `failure` takes down the link of `edge`, an edge of the node
+    // at `index`, the way `Failure::is_link_failed()` would.
+    fn is_edge_failed(
+        &self,
+        index: usize,
+        edges: &CsrEdges,
+        edge: usize,
+        direct: &SpfDirect,
+        failure: &Failure,
+    ) -> bool {
+        let (from, to) = match direct {
+            SpfDirect::Normal => (index, edges.targets[edge] as usize),
+            SpfDirect::Reverse => (edges.targets[edge] as usize, index),
+        };
+        let (from, to) = (self.ids[from], self.ids[to]);
+        if failure.is_node_failed(from) || failure.is_node_failed(to) {
+            return true;
+        }
+        let attrs = &self.attrs[edges.attrs[edge] as usize];
+        failure.links.iter().any(|link| {
+            link.from == from
+                && link.to == to
+                && link.cost == edges.costs[edge]
+                && attrs.is_of(link)
+        })
+    }
+
+    /// Index of the node at the other end and cost of each link of the
+    /// node at `index` in the SPF direction, links which are down
+    /// included.
+    pub fn index_edges(
+        &self,
+        index: usize,
+        direct: &SpfDirect,
+    ) -> impl Iterator<Item = (usize, u32)> + '_ {
+        let edges = self.edges(direct);
+        edges
+            .range(index)
+            .map(|edge| (edges.targets[edge] as usize, edges.costs[edge]))
+    }
+}
+
+impl From<&Graph> for CsrGraph {
+    fn from(graph: &Graph) -> Self {
+        CsrGraph::new(graph)
+    }
+}
+
+impl Topology for CsrGraph {
+    fn node_index(&self, id: usize) -> Option<usize> {
+        self.index(id)
+    }
+
+    fn node_id(&self, index: usize) -> usize {
+        self.ids[index]
+    }
+
+    fn is_node_disabled(&self, index: usize) -> bool {
+        self.disabled.get(index)
+    }
+
+    fn for_each_link<F: FnMut(usize, u32)>(
+        &self,
+        index: usize,
+        direct: &SpfDirect,
+        failure: &Failure,
+        mut f: F,
+    ) {
+        let edges = self.edges(direct);
+        for edge in edges.range(index) {
+            if edges.down.get(edge) {
+                continue;
+            }
+            if !failure.is_empty() && self.is_edge_failed(index, edges, edge, direct, failure) {
+                continue;
+            }
+            f(edges.targets[edge] as usize, edges.costs[edge]);
+        }
+    }
+}
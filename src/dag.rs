@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{Failure, SpfDirect, SpfQueue, Topology};
+
+/// Node of the shortest path DAG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DagNode {
+    pub cost: u32,
+    /// Previous hop of each shortest path link toward the node, in the
+    /// order SPF reached them. A parent linked by several parallel links
+    /// appears once per link.
+    pub parents: Vec<usize>,
+}
+
+/// Shortest path DAG: the cost of each reachable node and its parents.
+/// Paths, nexthops and path counts are derived from it on demand instead of
+/// being stored for every node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpfDag {
+    pub root: usize,
+    pub nodes: BTreeMap<usize, DagNode>,
+}
+
+/// Runs SPF from `root` keeping only the predecessors of each node.
+pub fn spf_dag<T: Topology + ?Sized>(graph: &T, root: usize, direct: &SpfDirect) -> SpfDag {
+    spf_dag_with(graph, root, direct, &Failure::default())
+}
+
+/// `spf_dag()` with the elements of `failure` treated as down, the same
+/// way as `spf_calc_with()`.
+pub fn spf_dag_with<T: Topology + ?Sized>(
+    graph: &T,
+    root: usize,
+    direct: &SpfDirect,
+    failure: &Failure,
+) -> SpfDag {
+    let mut nodes = BTreeMap::<usize, DagNode>::new();
+    // Nodes reached but not settled yet by index, as in `spf_calc_with()`.
+    let mut reached = HashMap::<usize, DagNode>::new();
+    let mut settled = HashSet::<usize>::new();
+    let mut queue = SpfQueue::new(false);
+
+    let root_node = DagNode {
+        cost: 0,
+        parents: Vec::new(),
+    };
+    let Some(root_index) = graph.node_index(root) else {
+        nodes.insert(root, root_node);
+        return SpfDag { root, nodes };
+    };
+    reached.insert(root_index, root_node);
+    queue.push(0, root_index);
+
+    while let Some((cost, index)) = queue.pop() {
+        // Skip the stale key of a node since reached at a lower cost.
+        match reached.get(&index) {
+            Some(node) if node.cost == cost => {}
+            _ => continue,
+        }
+        let Some(node) = reached.remove(&index) else {
+            continue;
+        };
+        let id = graph.node_id(index);
+        nodes.insert(id, node);
+        settled.insert(index);
+
+        if graph.is_node_disabled(index) || failure.is_node_failed(id) {
+            continue;
+        }
+
+        graph.for_each_link(index, direct, failure, |to, link_cost| {
+            if graph.is_node_disabled(to) || to == root_index || settled.contains(&to) {
+                return;
+            }
+
+            let to_cost = cost.saturating_add(link_cost);
+            match reached.get_mut(&to) {
+                Some(node) if node.cost < to_cost => {}
+                Some(node) if node.cost == to_cost => node.parents.push(id),
+                _ => {
+                    reached.insert(
+                        to,
+                        DagNode {
+                            cost: to_cost,
+                            parents: vec![id],
+                        },
+                    );
+                    queue.push(to_cost, to);
+                }
+            }
+        });
+    }
+
+    SpfDag { root, nodes }
+}
+
+impl SpfDag {
+    pub fn cost(&self, id: usize) -> Option<u32> {
+        self.nodes.get(&id).map(|node| node.cost)
+    }
+
+    /// First hops of the shortest paths toward `id`, empty for the root and
+    /// unreachable nodes.
+    pub fn nexthops(&self, id: usize) -> BTreeSet<usize> {
+        let mut nexthops = BTreeSet::new();
+        let mut visited = BTreeSet::new();
+        let mut stack = vec![id];
+
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            for parent in &node.parents {
+                if *parent == self.root {
+                    nexthops.insert(id);
+                } else {
+                    stack.push(*parent);
+                }
+            }
+        }
+        nexthops
+    }
+
+    /// Number of shortest paths toward `id`, saturating at `u64::MAX`.
+    pub fn path_count(&self, id: usize) -> u64 {
+        let mut counts = HashMap::<usize, u64>::new();
+        counts.insert(self.root, 1);
+
+        // Ancestors of `id` in SPF order, so that parents come first.
+        let mut ancestors = BTreeSet::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            if id != self.root && ancestors.insert((node.cost, id)) {
+                stack.extend(node.parents.iter().copied());
+            }
+        }
+
+        for (_, id) in ancestors {
+            let count = self.nodes[&id].parents.iter().fold(0u64, |acc, parent| {
+                acc.saturating_add(counts.get(parent).copied().unwrap_or(0))
+            });
+            counts.insert(id, count);
+        }
+        if self.nodes.contains_key(&id) {
+            counts[&id]
+        } else {
+            0
+        }
+    }
+
+    /// Iterates over the shortest paths toward `id`, root first, in the
+    /// order `spf_calc` lists them in full path mode.
+    pub fn paths(&self, id: usize) -> DagPaths<'_> {
+        let mut paths = DagPaths {
+            dag: self,
+            stack: Vec::new(),
+        };
+        if self.nodes.contains_key(&id) {
+            paths.stack.push((id, 0));
+            paths.descend();
+        }
+        paths
+    }
+}
+
+/// Lazy iterator over the shortest paths of a node, see `SpfDag::paths`.
+pub struct DagPaths<'a> {
+    dag: &'a SpfDag,
+    // Current path from the destination back to the root, each node with
+    // the index of the parent taken.
+    stack: Vec<(usize, usize)>,
+}
+
+impl DagPaths<'_> {
+    // Follows the parents taken down to the root.
+    fn descend(&mut self) {
+        while let Some((id, index)) = self.stack.last().copied() {
+            if id == self.dag.root {
+                break;
+            }
+            let parent = self.dag.nodes[&id].parents[index];
+            self.stack.push((parent, 0));
+        }
+    }
+}
+
+impl Iterator for DagPaths<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.stack.is_empty() {
+            return None;
+        }
+        let path = self.stack.iter().rev().map(|(id, _)| *id).collect();
+
+        // Take the next parent of the node closest to the root that has
+        // one left.
+        self.stack.pop();
+        while let Some((id, index)) = self.stack.last_mut() {
+            *index += 1;
+            if *index < self.dag.nodes[id].parents.len() {
+                self.descend();
+                break;
+            }
+            self.stack.pop();
+        }
+        Some(path)
+    }
+}
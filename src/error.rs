@@ -0,0 +1,90 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpfError {
+    /// The node id is not in the graph.
+    UnknownNode(usize),
+    /// The computing node is disabled or part of the failure.
+    RootDisabled(usize),
+    /// The destination is the computing node itself.
+    RootIsDestination(usize),
+    /// The nodes have no adjacency to protect.
+    NotAdjacent(usize, usize),
+    /// The destination is not reachable before the failure.
+    Unreachable(usize),
+    /// The destination is not reachable once the failure happened.
+    NoRepairPath(usize),
+    /// The post-convergence path handed to the repair list computation
+    /// still contains S or D, or a segment does not start where the previous
+    /// one ended.
+    InvalidPath(usize),
+    /// The post-convergence path is empty.
+    EmptyPath,
+    /// The node has no Prefix-SID, or no SRGB to resolve one.
+    NoPrefixSid(usize),
+    /// The adjacency has no Adj-SID.
+    NoAdjSid(usize, usize),
+    /// The node is not SRv6 capable, or has no locator or End SID.
+    NoEndSid(usize),
+    /// The adjacency has no End.X SID, or its node no locator.
+    NoEndXSid(usize, usize),
+    /// An SRv6 SID of the node or of one of its adjacencies is outside the
+    /// locator of the node.
+    SidOutsideLocator(usize),
+    /// The repair path needs a node that does not support the selected SR
+    /// dataplane.
+    NotSrCapable(usize),
+    /// The repair list needs more SIDs than the node can push.
+    MsdExceeded(usize),
+}
+
+impl SpfError {
+    /// Whether the error only means that no usable repair path exists for
+    /// the failure, so that protecting a smaller element may still work.
+    pub fn is_unprotectable(&self) -> bool {
+        matches!(
+            self,
+            SpfError::NoRepairPath(_) | SpfError::NotSrCapable(_) | SpfError::MsdExceeded(_)
+        )
+    }
+}
+
+impl fmt::Display for SpfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpfError::UnknownNode(id) => write!(f, "unknown node {}", id),
+            SpfError::RootDisabled(id) => write!(f, "node {} is disabled", id),
+            SpfError::RootIsDestination(id) => {
+                write!(f, "node {} is both the source and the destination", id)
+            }
+            SpfError::NotAdjacent(from, to) => {
+                write!(f, "node {} is not adjacent to node {}", to, from)
+            }
+            SpfError::Unreachable(id) => write!(f, "node {} is unreachable", id),
+            SpfError::NoRepairPath(id) => write!(f, "no repair path to node {}", id),
+            SpfError::InvalidPath(id) => {
+                write!(f, "invalid post-convergence path at node {}", id)
+            }
+            SpfError::EmptyPath => write!(f, "empty post-convergence path"),
+            SpfError::NoPrefixSid(id) => write!(f, "no Prefix-SID for node {}", id),
+            SpfError::NoAdjSid(from, to) => {
+                write!(f, "no Adj-SID for adjacency {} -> {}", from, to)
+            }
+            SpfError::NoEndSid(id) => write!(f, "no SRv6 End SID for node {}", id),
+            SpfError::NoEndXSid(from, to) => {
+                write!(f, "no SRv6 End.X SID for adjacency {} -> {}", from, to)
+            }
+            SpfError::SidOutsideLocator(id) => {
+                write!(f, "SRv6 SID of node {} is outside its locator", id)
+            }
+            SpfError::NotSrCapable(id) => {
+                write!(f, "node {} does not support the SR dataplane", id)
+            }
+            SpfError::MsdExceeded(id) => {
+                write!(f, "repair list exceeds the MSD of node {}", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpfError {}
@@ -0,0 +1,100 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Monotone priority queue of `(cost, id)` keys for integer costs. Keys
+/// pushed may not be lower than the last key popped, which holds for SPF
+/// as link costs are not negative. Keys of equal cost pop in ascending id
+/// order.
+#[derive(Debug, Clone)]
+pub struct RadixHeap {
+    last: u32,
+    len: usize,
+    // Bucket 0 holds the keys equal to `last` in descending id order, bucket
+    // i the keys whose highest bit differing from `last` is bit i - 1.
+    buckets: Vec<Vec<(u32, usize)>>,
+}
+
+impl Default for RadixHeap {
+    fn default() -> Self {
+        Self {
+            last: 0,
+            len: 0,
+            buckets: vec![Vec::new(); 33],
+        }
+    }
+}
+
+impl RadixHeap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bucket(&self, cost: u32) -> usize {
+        (32 - (cost ^ self.last).leading_zeros()) as usize
+    }
+
+    pub fn push(&mut self, cost: u32, id: usize) {
+        debug_assert!(cost >= self.last);
+        let bucket = self.bucket(cost);
+        if bucket == 0 {
+            let pos = self.buckets[0].partition_point(|(_, other)| *other > id);
+            self.buckets[0].insert(pos, (cost, id));
+        } else {
+            self.buckets[bucket].push((cost, id));
+        }
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<(u32, usize)> {
+        if self.buckets[0].is_empty() {
+            let index = self.buckets.iter().position(|bucket| !bucket.is_empty())?;
+            let keys = std::mem::take(&mut self.buckets[index]);
+            self.last = keys.iter().map(|(cost, _)| *cost).min()?;
+            for (cost, id) in keys {
+                let bucket = self.bucket(cost);
+                self.buckets[bucket].push((cost, id));
+            }
+            self.buckets[0].sort_by_key(|(_, id)| Reverse(*id));
+        }
+        self.len -= 1;
+        self.buckets[0].pop()
+    }
+}
+
+// Queue of `spf_calc()`.
+pub(crate) enum SpfQueue {
+    Binary(BinaryHeap<Reverse<(u32, usize)>>),
+    Radix(RadixHeap),
+}
+
+impl SpfQueue {
+    pub(crate) fn new(radix_heap: bool) -> Self {
+        if radix_heap {
+            SpfQueue::Radix(RadixHeap::new())
+        } else {
+            SpfQueue::Binary(BinaryHeap::new())
+        }
+    }
+
+    pub(crate) fn push(&mut self, cost: u32, id: usize) {
+        match self {
+            SpfQueue::Binary(heap) => heap.push(Reverse((cost, id))),
+            SpfQueue::Radix(heap) => heap.push(cost, id),
+        }
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<(u32, usize)> {
+        match self {
+            SpfQueue::Binary(heap) => heap.pop().map(|Reverse(key)| key),
+            SpfQueue::Radix(heap) => heap.pop(),
+        }
+    }
+}
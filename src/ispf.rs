@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{Graph, Link, Path, SpfDirect, SpfOpt, SpfQueue, spf_calc};
+
+/// Network elements whose state or cost changed since a previous SPF run:
+/// nodes added, removed, enabled or disabled, and links added, removed or
+/// modified. A modified link can be given with its old or its new values.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpfChange {
+    pub nodes: BTreeSet<usize>,
+    pub links: Vec<Link>,
+}
+
+impl SpfChange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, id: usize) {
+        self.nodes.insert(id);
+    }
+
+    pub fn add_link(&mut self, link: Link) {
+        self.links.push(link);
+    }
+}
+
+// Whether SPF goes on from the node.
+fn expands(graph: &Graph, id: usize) -> bool {
+    graph.get(&id).is_some_and(|node| !node.is_disabled)
+}
+
+// Nodes with a link toward `id` in the SPF direction, taken from the
+// adjacency list of `id` opposite to the one SPF walks.
+fn tails(graph: &Graph, id: usize, direct: &SpfDirect) -> BTreeSet<usize> {
+    let Some(node) = graph.get(&id) else {
+        return BTreeSet::new();
+    };
+    match direct {
+        SpfDirect::Normal => node.ilinks.iter().map(|link| link.from).collect(),
+        SpfDirect::Reverse => node.olinks.iter().map(|link| link.to).collect(),
+    }
+}
+
+// Active links from `from` to `to` in the SPF direction.
+fn links_to<'a>(
+    graph: &'a Graph,
+    from: usize,
+    to: usize,
+    direct: &'a SpfDirect,
+) -> impl Iterator<Item = &'a Link> {
+    graph
+        .get(&from)
+        .into_iter()
+        .flat_map(move |node| node.links(direct).iter())
+        .filter(move |link| link.id(direct) == to && link.is_active())
+}
+
+// Adds to `affected` the nodes of `seeds` and everything below them in the
+// previous shortest path DAG. Link state is ignored, an edge that may have
+// carried a shortest path is enough.
+fn descendants(
+    graph: &Graph,
+    root: usize,
+    direct: &SpfDirect,
+    prev: &BTreeMap<usize, Path>,
+    seeds: impl IntoIterator<Item = usize>,
+    affected: &mut BTreeSet<usize>,
+) {
+    let mut stack = seeds
+        .into_iter()
+        .filter(|id| *id != root)
+        .collect::<Vec<_>>();
+
+    while let Some(id) = stack.pop() {
+        if !affected.insert(id) {
+            continue;
+        }
+        let (Some(path), Some(node)) = (prev.get(&id), graph.get(&id)) else {
+            continue;
+        };
+        for link in node.links(direct) {
+            let to = link.id(direct);
+            if to == root || affected.contains(&to) {
+                continue;
+            }
+            if prev
+                .get(&to)
+                .is_some_and(|child| path.cost.saturating_add(link.cost) == child.cost)
+            {
+                stack.push(to);
+            }
+        }
+    }
+}
+
+// Result of a node whose cost is known, built from its predecessors the
+// way `spf_calc()` accumulates them: in the order they are taken off the
+// queue, once per link of the shortest cost.
+fn node_path(
+    graph: &Graph,
+    root: usize,
+    opt: &SpfOpt,
+    direct: &SpfDirect,
+    spf: &BTreeMap<usize, Path>,
+    id: usize,
+    cost: u32,
+) -> Path {
+    let mut preds = tails(graph, id, direct)
+        .into_iter()
+        .filter(|pred| expands(graph, *pred))
+        .filter_map(|pred| spf.get(&pred))
+        .collect::<Vec<_>>();
+    preds.sort_by_key(|pred| (pred.cost, pred.id));
+
+    let mut c = Path::new(id);
+    c.cost = cost;
+    c.registered = true;
+
+    for pred in preds {
+        for _ in links_to(graph, pred.id, id, direct)
+            .filter(|link| pred.cost.saturating_add(link.cost) == cost)
+        {
+            if pred.id == root {
+                let path = vec![root, id];
+
+                if opt.full_path {
+                    c.paths.push(path);
+                } else {
+                    c.nexthops.insert(path);
+                }
+            } else if opt.full_path {
+                for path in &pred.paths {
+                    if opt.path_max == 0 || c.paths.len() < opt.path_max {
+                        let mut newpath = path.clone();
+                        newpath.push(id);
+                        c.paths.push(newpath);
+                    }
+                }
+            } else {
+                for nhop in &pred.nexthops {
+                    if opt.path_max == 0 || c.nexthops.len() < opt.path_max {
+                        let mut newnhop = nhop.clone();
+                        if nhop.len() < 2 {
+                            newnhop.push(id);
+                        }
+                        c.nexthops.insert(newnhop);
+                    }
+                }
+            }
+        }
+    }
+    c
+}
+
+// Dijkstra over the affected nodes, updating their entries of `spf` in
+// place while the others keep their previous result. Returns the unaffected
+// nodes that turn out to get a new or an equal cost path through an
+// affected one, `spf` being then restored to its previous content.
+fn ispf_run(
+    graph: &Graph,
+    root: usize,
+    opt: &SpfOpt,
+    direct: &SpfDirect,
+    spf: &mut BTreeMap<usize, Path>,
+    affected: &BTreeSet<usize>,
+) -> Result<(), BTreeSet<usize>> {
+    let prev = affected
+        .iter()
+        .filter_map(|id| spf.remove(id).map(|path| (*id, path)))
+        .collect::<BTreeMap<_, _>>();
+    let mut added = BTreeSet::new();
+    let mut costs = HashMap::<usize, u32>::new();
+    let mut queue = SpfQueue::new(opt.radix_heap);
+    let mut unstable = BTreeSet::new();
+
+    let relax = |costs: &mut HashMap<usize, u32>, queue: &mut SpfQueue, id, cost| {
+        let current = costs.entry(id).or_insert(u32::MAX);
+        if cost < *current {
+            *current = cost;
+            queue.push(cost, id);
+        }
+    };
+
+    // Entry points of the affected area.
+    for id in affected {
+        if graph.get(id).is_some_and(|node| node.is_disabled) {
+            continue;
+        }
+        for pred in tails(graph, *id, direct) {
+            let Some(path) = spf.get(&pred) else {
+                continue;
+            };
+            if !expands(graph, pred) {
+                continue;
+            }
+            for link in links_to(graph, pred, *id, direct) {
+                relax(
+                    &mut costs,
+                    &mut queue,
+                    *id,
+                    path.cost.saturating_add(link.cost),
+                );
+            }
+        }
+    }
+
+    while let Some((cost, id)) = queue.pop() {
+        // Skip the stale key of a node since reached at a lower cost.
+        if costs.get(&id) != Some(&cost) || added.contains(&id) {
+            continue;
+        }
+        let path = node_path(graph, root, opt, direct, spf, id, cost);
+        spf.insert(id, path);
+        added.insert(id);
+
+        if !expands(graph, id) {
+            continue;
+        }
+        for link in graph[&id].links(direct) {
+            let to = link.id(direct);
+            if to == root || !link.is_active() {
+                continue;
+            }
+            if graph.get(&to).is_some_and(|node| node.is_disabled) {
+                continue;
+            }
+            let to_cost = cost.saturating_add(link.cost);
+            if !added.contains(&to) && !affected.contains(&to) {
+                // Reached by the previous run and left as it was.
+                if let Some(stable) = spf.get(&to) {
+                    if to_cost <= stable.cost {
+                        unstable.insert(to);
+                    }
+                    continue;
+                }
+            }
+            if !spf.contains_key(&to) {
+                relax(&mut costs, &mut queue, to, to_cost);
+            }
+        }
+    }
+
+    if unstable.is_empty() {
+        Ok(())
+    } else {
+        for id in &added {
+            spf.remove(id);
+        }
+        spf.extend(prev);
+        Err(unstable)
+    }
+}
+
+/// Incremental SPF: updates `prev`, the result of `spf_calc()` with the
+/// same root, options and direction, after the elements of `change` were
+/// modified in `graph`. Entries are replaced in place, so that the cost
+/// follows the size of the recomputed area rather than of the graph. Only the nodes below the changed elements in the
+/// previous shortest path tree are recomputed, growing that set when a
+/// change also improves nodes outside of it. Removing a node that was
+/// reached falls back to a full run. The result is the one of a
+/// full run on `graph` as long as link costs are not zero and the `ilinks`
+/// of each node mirror the `olinks` of the others, as `GraphBuilder` keeps
+/// them.
+pub fn ispf_calc(
+    graph: &Graph,
+    root: usize,
+    opt: &SpfOpt,
+    direct: &SpfDirect,
+    mut prev: BTreeMap<usize, Path>,
+    change: &SpfChange,
+) -> BTreeMap<usize, Path> {
+    if change.nodes.contains(&root) || !prev.contains_key(&root) {
+        return spf_calc(graph, root, opt, direct);
+    }
+
+    let seeds = change
+        .nodes
+        .iter()
+        .copied()
+        .chain(change.links.iter().map(|link| link.id(direct)))
+        .collect::<Vec<_>>();
+
+    // The children of a node removed from the graph can no longer be found
+    // through its links.
+    if seeds
+        .iter()
+        .any(|id| prev.contains_key(id) && !graph.contains_key(id))
+    {
+        return spf_calc(graph, root, opt, direct);
+    }
+
+    let mut affected = BTreeSet::new();
+    descendants(graph, root, direct, &prev, seeds, &mut affected);
+
+    loop {
+        match ispf_run(graph, root, opt, direct, &mut prev, &affected) {
+            Ok(()) => return prev,
+            Err(unstable) => descendants(graph, root, direct, &prev, unstable, &mut affected),
+        }
+    }
+}
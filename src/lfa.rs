@@ -0,0 +1,136 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Graph, Path, SpfOpt, spf};
+
+/// Loop-free alternate of S for one destination and primary nexthop
+/// (RFC 5286).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lfa {
+    /// Primary nexthop E protected by the alternate.
+    pub primary: usize,
+    /// Alternate neighbor N.
+    pub alternate: usize,
+    /// Inequality 1: D(N,D) < D(N,S) + D(S,D).
+    pub link_protecting: bool,
+    /// Inequality 3: D(N,D) < D(N,E) + D(E,D).
+    pub node_protecting: bool,
+    /// Inequality 2: D(N,D) < D(S,D).
+    pub downstream: bool,
+    /// N is itself a primary nexthop of S toward D.
+    pub ecmp: bool,
+}
+
+pub(crate) fn distance(spf: &BTreeMap<usize, Path>, id: usize) -> Option<u32> {
+    spf.get(&id).map(|path| path.cost)
+}
+
+// Cheapest usable adjacency from S to each of its neighbors.
+pub(crate) fn neighbors(graph: &Graph, s: usize) -> BTreeMap<usize, u32> {
+    let mut neighbors = BTreeMap::new();
+
+    let Some(node) = graph.get(&s) else {
+        return neighbors;
+    };
+
+    for link in &node.olinks {
+        if link.to == s || !link.is_active() || graph.get(&link.to).is_some_and(|n| n.is_disabled) {
+            continue;
+        }
+        neighbors
+            .entry(link.to)
+            .and_modify(|cost: &mut u32| *cost = (*cost).min(link.cost))
+            .or_insert(link.cost);
+    }
+    neighbors
+}
+
+// Primary nexthops are the neighbors on a shortest path to D.
+pub(crate) fn primaries(
+    neighbors: &BTreeMap<usize, u32>,
+    nbr_spf: &BTreeMap<usize, BTreeMap<usize, Path>>,
+    d: usize,
+    s_d: u32,
+) -> BTreeSet<usize> {
+    neighbors
+        .iter()
+        .filter(|(n, cost)| {
+            nbr_spf
+                .get(n)
+                .and_then(|spf| distance(spf, d))
+                .is_some_and(|n_d| cost.saturating_add(n_d) == s_d)
+        })
+        .map(|(n, _)| *n)
+        .collect()
+}
+
+/// Computes the LFAs of S for every destination, from the SPF rooted at S
+/// and the SPF rooted at each neighbor of S. Destinations without any
+/// alternate are reported with an empty list.
+pub fn lfa_calc(
+    graph: &Graph,
+    s: usize,
+    root_spf: &BTreeMap<usize, Path>,
+    nbr_spf: &BTreeMap<usize, BTreeMap<usize, Path>>,
+) -> BTreeMap<usize, Vec<Lfa>> {
+    let neighbors = neighbors(graph, s);
+    let mut lfas = BTreeMap::new();
+
+    for (d, path) in root_spf {
+        if *d == s {
+            continue;
+        }
+        let s_d = path.cost;
+
+        let primaries = primaries(&neighbors, nbr_spf, *d, s_d);
+
+        let mut alternates = Vec::new();
+
+        for e in &primaries {
+            let e_d = nbr_spf.get(e).and_then(|spf| distance(spf, *d));
+
+            for n in neighbors.keys().filter(|n| *n != e) {
+                let Some(spf) = nbr_spf.get(n) else {
+                    continue;
+                };
+                let (Some(n_d), Some(n_s)) = (distance(spf, *d), distance(spf, s)) else {
+                    continue;
+                };
+
+                // Inequality 1 decides whether N is loop-free at all.
+                if n_d >= n_s.saturating_add(s_d) {
+                    continue;
+                }
+
+                let node_protecting = *d != *e
+                    && match (distance(spf, *e), e_d) {
+                        (Some(n_e), Some(e_d)) => n_d < n_e.saturating_add(e_d),
+                        _ => true,
+                    };
+
+                alternates.push(Lfa {
+                    primary: *e,
+                    alternate: *n,
+                    link_protecting: true,
+                    node_protecting,
+                    downstream: n_d < s_d,
+                    ecmp: primaries.contains(n),
+                });
+            }
+        }
+        lfas.insert(*d, alternates);
+    }
+    lfas
+}
+
+/// Runs the SPF from S and from each of its neighbors and computes the LFAs
+/// of S for every destination.
+pub fn lfa(graph: &Graph, s: usize) -> BTreeMap<usize, Vec<Lfa>> {
+    let opt = SpfOpt::default();
+    let root_spf = spf(graph, s, &opt);
+    let nbr_spf = neighbors(graph, s)
+        .into_keys()
+        .map(|n| (n, spf(graph, n, &opt)))
+        .collect::<BTreeMap<_, _>>();
+
+    lfa_calc(graph, s, &root_spf, &nbr_spf)
+}
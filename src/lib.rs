@@ -1,5 +1,54 @@
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::Ipv6Addr;
+
+mod all_pairs;
+pub use all_pairs::*;
+
+mod builder;
+pub use builder::*;
+
+mod coverage;
+pub use coverage::*;
+
+mod csr;
+pub use csr::*;
+
+mod dag;
+pub use dag::*;
+
+mod error;
+pub use error::*;
+
+mod heap;
+pub use heap::*;
+
+mod ispf;
+pub use ispf::*;
+
+mod lfa;
+pub use lfa::*;
+
+mod microloop;
+pub use microloop::*;
+
+mod mpls;
+pub use mpls::*;
+
+mod srv6;
+pub use srv6::*;
+
+mod ucmp;
+pub use ucmp::*;
+
+mod usid;
+pub use usid::*;
+
+mod rlfa;
+pub use rlfa::*;
+
+mod route;
+pub use route::*;
 
 pub type Graph = BTreeMap<usize, Node>;
 
@@ -7,8 +56,14 @@ pub type Graph = BTreeMap<usize, Node>;
 pub struct SpfOpt {
     pub full_path: bool,
     pub path_max: usize,
+    /// Only use nodes supporting SR-MPLS in TI-LFA repair lists, handed to
+    /// `tilfa()` through `TilfaOpt::from()`.
     pub srmpls: bool,
+    /// Only use nodes supporting SRv6 in TI-LFA repair lists, handed to
+    /// `tilfa()` through `TilfaOpt::from()`.
     pub srv6: bool,
+    /// Use a radix heap instead of a binary heap as the SPF queue.
+    pub radix_heap: bool,
 }
 
 impl SpfOpt {
@@ -26,6 +81,22 @@ pub struct Node {
     pub is_disabled: bool,
     pub is_srv6: bool,
     pub is_srmpls: bool,
+    /// SR-MPLS global block.
+    pub srgb: Option<Srgb>,
+    /// Prefix-SID index of the node's loopback.
+    pub prefix_sid: Option<u32>,
+    /// Prefix-SID flags: the penultimate hop must not pop the label.
+    pub no_php: bool,
+    /// Prefix-SID flags: the penultimate hop swaps to explicit-null.
+    pub explicit_null: bool,
+    /// SRv6 locator, the End and End.X SIDs of the node must fall in it.
+    pub srv6_locator: Option<Srv6Locator>,
+    /// SRv6 End SID.
+    pub end_sid: Option<Ipv6Addr>,
+    /// Maximum SID depth the node can push, unlimited when `None`.
+    pub msd: Option<usize>,
+    /// Prefixes advertised by the node with their metric.
+    pub prefixes: BTreeMap<Prefix, u32>,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -44,6 +115,14 @@ impl Node {
             is_disabled: false,
             is_srv6: true,
             is_srmpls: true,
+            srgb: None,
+            prefix_sid: None,
+            no_php: false,
+            explicit_null: false,
+            srv6_locator: None,
+            end_sid: None,
+            msd: None,
+            prefixes: BTreeMap::new(),
         }
     }
 
@@ -69,11 +148,50 @@ pub struct Link {
     pub from: usize,
     pub to: usize,
     pub cost: u32,
+    /// Shared risk link groups the link belongs to.
+    pub srlgs: Vec<u32>,
+    /// Operational state.
+    pub is_up: bool,
+    /// Administrative state.
+    pub is_disabled: bool,
+    /// SR-MPLS Adj-SID label.
+    pub adj_sid: Option<u32>,
+    /// SRv6 End.X SID.
+    pub end_x_sid: Option<Ipv6Addr>,
+    /// Bandwidth used to weight UCMP nexthops.
+    pub bandwidth: Option<u64>,
 }
 
 impl Link {
     pub fn new(from: usize, to: usize, cost: u32) -> Self {
-        Self { from, to, cost }
+        Self {
+            from,
+            to,
+            cost,
+            srlgs: Vec::new(),
+            is_up: true,
+            is_disabled: false,
+            adj_sid: None,
+            end_x_sid: None,
+            bandwidth: None,
+        }
+    }
+
+    /// Whether SPF may use the link.
+    pub fn is_active(&self) -> bool {
+        self.is_up && !self.is_disabled
+    }
+
+    // Parallel links are told apart by their attributes, not by their
+    // state. `CsrGraph` compares the same fields.
+    pub(crate) fn is_same(&self, other: &Link) -> bool {
+        self.from == other.from
+            && self.to == other.to
+            && self.cost == other.cost
+            && self.srlgs == other.srlgs
+            && self.adj_sid == other.adj_sid
+            && self.end_x_sid == other.end_x_sid
+            && self.bandwidth == other.bandwidth
     }
 
     pub fn id(&self, direct: &SpfDirect) -> usize {
@@ -85,6 +203,37 @@ impl Link {
     }
 }
 
+// Applies `f` to every copy of `link`: the one in the `olinks` of its
+// source and the one in the `ilinks` of its destination.
+fn link_update(graph: &mut Graph, link: &Link, f: impl Fn(&mut Link)) -> bool {
+    let mut found = false;
+    if let Some(node) = graph.get_mut(&link.from) {
+        for copy in node.olinks.iter_mut().filter(|l| l.is_same(link)) {
+            f(copy);
+            found = true;
+        }
+    }
+    if let Some(node) = graph.get_mut(&link.to) {
+        for copy in node.ilinks.iter_mut().filter(|l| l.is_same(link)) {
+            f(copy);
+            found = true;
+        }
+    }
+    found
+}
+
+/// Sets the operational state of `link` in both adjacency lists. Returns
+/// false when the graph has no such link.
+pub fn link_set_up(graph: &mut Graph, link: &Link, is_up: bool) -> bool {
+    link_update(graph, link, |copy| copy.is_up = is_up)
+}
+
+/// Sets the administrative state of `link` in both adjacency lists.
+/// Returns false when the graph has no such link.
+pub fn link_set_disabled(graph: &mut Graph, link: &Link, is_disabled: bool) -> bool {
+    link_update(graph, link, |copy| copy.is_disabled = is_disabled)
+}
+
 impl Ord for Path {
     fn cmp(&self, other: &Self) -> Ordering {
         other.cost.cmp(&self.cost)
@@ -118,62 +267,141 @@ impl Path {
     }
 }
 
-pub fn spf_calc(
-    graph: &Graph,
+/// Graph representation SPF can run on. SPF works on internal node
+/// indices and maps them back to node ids only in its result.
+pub trait Topology {
+    /// Internal index of node `id`, `None` when there is no such node.
+    fn node_index(&self, id: usize) -> Option<usize>;
+
+    /// Node id of internal index `index`.
+    fn node_id(&self, index: usize) -> usize;
+
+    /// Administrative state of the node at `index`.
+    fn is_node_disabled(&self, index: usize) -> bool;
+
+    /// Calls `f` with the index of the node at the other end and the cost
+    /// of each link of the node at `index` in the SPF direction, skipping
+    /// the links which are down or taken down by `failure`.
+    fn for_each_link<F: FnMut(usize, u32)>(
+        &self,
+        index: usize,
+        direct: &SpfDirect,
+        failure: &Failure,
+        f: F,
+    );
+}
+
+// A node id is its own index.
+impl Topology for Graph {
+    fn node_index(&self, id: usize) -> Option<usize> {
+        self.contains_key(&id).then_some(id)
+    }
+
+    fn node_id(&self, index: usize) -> usize {
+        index
+    }
+
+    fn is_node_disabled(&self, index: usize) -> bool {
+        self.get(&index).is_some_and(|node| node.is_disabled)
+    }
+
+    fn for_each_link<F: FnMut(usize, u32)>(
+        &self,
+        index: usize,
+        direct: &SpfDirect,
+        failure: &Failure,
+        mut f: F,
+    ) {
+        let Some(node) = self.get(&index) else {
+            return;
+        };
+        for link in node.links(direct) {
+            if link.is_active() && !failure.is_link_failed(link) {
+                f(link.id(direct), link.cost);
+            }
+        }
+    }
+}
+
+pub fn spf_calc<T: Topology + ?Sized>(
+    graph: &T,
     root: usize,
     opt: &SpfOpt,
     direct: &SpfDirect,
+) -> BTreeMap<usize, Path> {
+    spf_calc_with(graph, root, opt, direct, &Failure::default())
+}
+
+/// SPF with the elements of `failure` treated as down, leaving `graph`
+/// untouched.
+pub fn spf_calc_with<T: Topology + ?Sized>(
+    graph: &T,
+    root: usize,
+    opt: &SpfOpt,
+    direct: &SpfDirect,
+    failure: &Failure,
 ) -> BTreeMap<usize, Path> {
     let mut spf = BTreeMap::<usize, Path>::new();
+    // State of the nodes reached but not settled yet by index, the queue
+    // only holds their indices.
     let mut paths = HashMap::<usize, Path>::new();
-    let mut bt = BTreeMap::<(u32, usize), Path>::new();
+    let mut settled = HashSet::<usize>::new();
+    let mut queue = SpfQueue::new(opt.radix_heap);
 
     let mut c = Path::new(root);
     c.paths.push(vec![root]);
     c.nexthops.insert(vec![root]);
 
-    paths.insert(root, c.clone());
-    bt.insert((c.cost, root), c);
-
-    while let Some((_, v)) = bt.pop_first() {
-        spf.insert(v.id, v.clone());
-
-        let Some(edge) = graph.get(&v.id) else {
+    let Some(root_index) = graph.node_index(root) else {
+        spf.insert(root, c);
+        return spf;
+    };
+    paths.insert(root_index, c);
+    queue.push(0, root_index);
+
+    while let Some((cost, index)) = queue.pop() {
+        // Skip the stale key of a node since reached at a lower cost.
+        match paths.get(&index) {
+            Some(v) if v.cost == cost => {}
+            _ => continue,
+        }
+        let Some(v) = paths.remove(&index) else {
             continue;
         };
+        let id = v.id;
+        spf.insert(id, v);
+        settled.insert(index);
+        let v = &spf[&id];
 
-        if edge.is_disabled {
+        if graph.is_node_disabled(index) || failure.is_node_failed(id) {
             continue;
         }
 
-        for link in edge.links(direct).iter() {
-            if let Some(x) = graph.get(&link.id(direct)) {
-                if x.is_disabled {
-                    continue;
-                }
-            };
-
-            let c = paths
-                .entry(link.id(direct))
-                .or_insert_with(|| Path::new(link.id(direct)));
-
-            let ocost = c.cost;
-
-            if c.id == root {
-                continue;
+        graph.for_each_link(index, direct, failure, |to, link_cost| {
+            if graph.is_node_disabled(to) {
+                return;
             }
 
-            if c.cost != 0 && c.cost < v.cost + link.cost {
-                continue;
+            if to == root_index || settled.contains(&to) {
+                return;
             }
 
-            if c.cost != 0 && c.cost == v.cost + link.cost {
-                // Fall through for ECMP.
+            let c = paths
+                .entry(to)
+                .or_insert_with(|| Path::new(graph.node_id(to)));
+            let cost = v.cost.saturating_add(link_cost);
+
+            if c.registered && c.cost < cost {
+                return;
             }
 
-            if c.cost == 0 || c.cost > v.cost + link.cost {
-                c.cost = v.cost.saturating_add(link.cost);
+            // Equal cost falls through for ECMP.
+            if !c.registered || c.cost > cost {
+                c.cost = cost;
                 c.paths.clear();
+                c.nexthops.clear();
+                c.registered = true;
+                queue.push(cost, to);
             }
 
             if v.id == root {
@@ -203,32 +431,20 @@ pub fn spf_calc(
                     }
                 }
             }
-
-            if !c.registered {
-                c.registered = true;
-                bt.insert((c.cost, c.id), c.clone());
-            } else if ocost == c.cost {
-                if let Some(v) = bt.get_mut(&(c.cost, c.id)) {
-                    if opt.full_path {
-                        v.paths = c.paths.clone();
-                    } else {
-                        v.nexthops = c.nexthops.clone();
-                    }
-                }
-            } else {
-                bt.remove(&(ocost, c.id));
-                bt.insert((c.cost, c.id), c.clone());
-            }
-        }
+        });
     }
     spf
 }
 
-pub fn spf(graph: &Graph, root: usize, opt: &SpfOpt) -> BTreeMap<usize, Path> {
+pub fn spf<T: Topology + ?Sized>(graph: &T, root: usize, opt: &SpfOpt) -> BTreeMap<usize, Path> {
     spf_calc(graph, root, opt, &SpfDirect::Normal)
 }
 
-pub fn spf_reverse(graph: &Graph, root: usize, opt: &SpfOpt) -> BTreeMap<usize, Path> {
+pub fn spf_reverse<T: Topology + ?Sized>(
+    graph: &T,
+    root: usize,
+    opt: &SpfOpt,
+) -> BTreeMap<usize, Path> {
     spf_calc(graph, root, opt, &SpfDirect::Reverse)
 }
 
@@ -236,55 +452,265 @@ pub fn path_has_x(path: &[usize], x: usize) -> bool {
     path.contains(&x)
 }
 
-pub fn p_space_nodes(graph: &Graph, s: usize, x: usize) -> HashSet<usize> {
-    let spf = spf(graph, s, &SpfOpt::default());
+/// Network elements taken down for a post-convergence computation. It is
+/// an overlay: `spf_calc_with()` skips the failed elements without
+/// modifying the graph.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub nodes: BTreeSet<usize>,
+    pub links: Vec<Link>,
+}
 
-    spf.iter()
-        .filter_map(|(node, path)| {
-            if *node == s {
-                return None; // Skip the source node
+impl Failure {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Failure of node `id` and therefore of every link attached to it.
+    pub fn node(id: usize) -> Self {
+        let mut failure = Self::new();
+        failure.add_node(id);
+        failure
+    }
+
+    /// Failure of every adjacency between `from` and `to`, in both
+    /// directions.
+    pub fn link(graph: &Graph, from: usize, to: usize) -> Self {
+        let mut failure = Self::new();
+        for (a, b) in [(from, to), (to, from)] {
+            if let Some(node) = graph.get(&a) {
+                for link in node.olinks.iter().filter(|link| link.to == b) {
+                    failure.add_link(link.clone());
+                }
             }
-            let has_valid_paths = path.paths.iter().any(|p| !path_has_x(p, x));
-            if has_valid_paths {
-                Some(*node)
-            } else {
-                None
+        }
+        failure
+    }
+
+    /// Failure of the adjacencies between `from` and `to` together with
+    /// every link sharing a risk group with them.
+    pub fn srlg(graph: &Graph, from: usize, to: usize) -> Self {
+        let mut failure = Self::link(graph, from, to);
+        let srlgs = failure
+            .links
+            .iter()
+            .flat_map(|link| link.srlgs.iter().copied())
+            .collect::<BTreeSet<_>>();
+        for srlg in srlgs {
+            failure.add_srlg(graph, srlg);
+        }
+        failure
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.links.is_empty()
+    }
+
+    pub fn add_node(&mut self, id: usize) {
+        self.nodes.insert(id);
+    }
+
+    /// Fails every link of the graph which belongs to `srlg`.
+    pub fn add_srlg(&mut self, graph: &Graph, srlg: u32) {
+        for node in graph.values() {
+            for link in node.olinks.iter().filter(|link| link.srlgs.contains(&srlg)) {
+                self.add_link(link.clone());
             }
-        })
-        .collect::<HashSet<_>>() // Collect into HashSet instead of Vec
+        }
+    }
+
+    pub fn add_link(&mut self, link: Link) {
+        if !self.links.iter().any(|l| l.is_same(&link)) {
+            self.links.push(link);
+        }
+    }
+
+    pub fn is_node_failed(&self, id: usize) -> bool {
+        self.nodes.contains(&id)
+    }
+
+    pub fn is_link_failed(&self, link: &Link) -> bool {
+        self.links.iter().any(|l| l.is_same(link))
+            || self.is_node_failed(link.from)
+            || self.is_node_failed(link.to)
+    }
+
+    // A hop of a shortest path is broken when every link that can carry it,
+    // i.e. each `from` -> `to` link of the hop's cost, has failed.
+    fn is_hop_failed(&self, graph: &Graph, from: usize, to: usize, cost: u32) -> bool {
+        let Some(node) = graph.get(&from) else {
+            return false;
+        };
+        let mut links = node
+            .olinks
+            .iter()
+            .filter(|link| link.to == to && link.cost == cost)
+            .peekable();
+        links.peek().is_some() && links.all(|link| self.is_link_failed(link))
+    }
 }
 
-pub fn q_space_nodes(graph: &Graph, d: usize, x: usize) -> HashSet<usize> {
-    let spf = spf_reverse(graph, d, &SpfOpt::default());
+/// Element protected by TI-LFA for a given neighbor X of S.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+    /// Protect against the failure of the S-X adjacency.
+    #[default]
+    Link,
+    /// Protect against the failure of X itself.
+    Node,
+    /// Protect against the failure of every link sharing a risk group with
+    /// the S-X adjacency.
+    Srlg,
+}
 
-    spf.iter()
-        .filter_map(|(node, path)| {
-            if *node == d {
-                return None; // Skip the source node
+// Nodes of the SPF DAG whose every shortest path from the root avoids the
+// failure. ECMP paths count as well (RFC 7490 section 2), so a node reachable
+// over one path through the failure is excluded even when another path
+// avoids it. A node qualifies when none of its parent hops is broken and all
+// of its parents qualify, which a single walk over the DAG decides.
+fn space_nodes(
+    graph: &Graph,
+    dag: &SpfDag,
+    failure: &Failure,
+    direct: &SpfDirect,
+) -> HashSet<usize> {
+    let mut avoids = HashMap::<usize, bool>::new();
+    avoids.insert(dag.root, !failure.is_node_failed(dag.root));
+
+    for id in dag.nodes.keys() {
+        // Parents are decided before their children.
+        let mut stack = vec![*id];
+        while let Some(id) = stack.last().copied() {
+            if avoids.contains_key(&id) {
+                stack.pop();
+                continue;
             }
-            let has_valid_paths = path.paths.iter().any(|p| !path_has_x(p, x));
-            if has_valid_paths {
-                Some(*node)
-            } else {
-                None
+            let node = &dag.nodes[&id];
+            let pending = node
+                .parents
+                .iter()
+                .filter(|parent| !avoids.contains_key(parent))
+                .copied()
+                .collect::<Vec<_>>();
+            if !pending.is_empty() {
+                stack.extend(pending);
+                continue;
             }
-        })
-        .collect::<HashSet<_>>()
+
+            let valid = !failure.is_node_failed(id)
+                && !node.parents.is_empty()
+                && node.parents.iter().all(|parent| {
+                    let (from, to) = if *direct == SpfDirect::Normal {
+                        (*parent, id)
+                    } else {
+                        (id, *parent)
+                    };
+                    let cost = node.cost.saturating_sub(dag.nodes[parent].cost);
+                    avoids[parent] && !failure.is_hop_failed(graph, from, to, cost)
+                });
+            avoids.insert(id, valid);
+            stack.pop();
+        }
+    }
+
+    avoids
+        .into_iter()
+        .filter(|(id, valid)| *valid && *id != dag.root)
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// P space of S: nodes reachable from S on pre-convergence shortest paths
+/// without any of those paths transiting the failure.
+pub fn p_space_nodes(graph: &Graph, s: usize, failure: &Failure) -> HashSet<usize> {
+    let dag = spf_dag(graph, s, &SpfDirect::Normal);
+    space_nodes(graph, &dag, failure, &SpfDirect::Normal)
 }
 
-pub fn pc_paths(graph: &Graph, s: usize, d: usize, x: usize) -> Vec<Vec<usize>> {
-    let mut pc_graph: Graph = graph.to_owned(); // Clone only when necessary
+/// Extended P space of S: the union of the P spaces of S and of every
+/// neighbor S still reaches over a working adjacency, those neighbors
+/// included (RFC 7490 section 4.2). S reaches a neighbor's P space by
+/// forwarding over that adjacency first.
+pub fn extended_p_space_nodes(graph: &Graph, s: usize, failure: &Failure) -> HashSet<usize> {
+    let mut p_nodes = p_space_nodes(graph, s, failure);
+
+    let Some(node) = graph.get(&s) else {
+        return p_nodes;
+    };
+
+    let neighbors = node
+        .olinks
+        .iter()
+        .filter(|link| link.to != s && link.is_active() && !failure.is_link_failed(link))
+        .filter(|link| !graph.get(&link.to).is_some_and(|node| node.is_disabled))
+        .map(|link| link.to)
+        .collect::<BTreeSet<_>>();
+
+    for neighbor in neighbors {
+        p_nodes.extend(p_space_nodes(graph, neighbor, failure));
+        p_nodes.insert(neighbor);
+    }
+    p_nodes.remove(&s);
+
+    p_nodes
+}
 
-    if let Some(x_node) = pc_graph.get_mut(&x) {
-        x_node.is_disabled = true;
+/// Q space of D: nodes that reach D on pre-convergence shortest paths
+/// without any of those paths transiting the failure. Computed with a
+/// reverse SPF rooted at D.
+pub fn q_space_nodes(graph: &Graph, d: usize, failure: &Failure) -> HashSet<usize> {
+    let dag = spf_dag(graph, d, &SpfDirect::Reverse);
+    space_nodes(graph, &dag, failure, &SpfDirect::Reverse)
+}
+
+// S must be a working node of the graph and D another known one.
+fn check_ends(graph: &Graph, s: usize, d: usize, failure: &Failure) -> Result<(), SpfError> {
+    let Some(node) = graph.get(&s) else {
+        return Err(SpfError::UnknownNode(s));
+    };
+    if !graph.contains_key(&d) {
+        return Err(SpfError::UnknownNode(d));
+    }
+    if d == s {
+        return Err(SpfError::RootIsDestination(s));
+    }
+    if node.is_disabled || failure.is_node_failed(s) {
+        return Err(SpfError::RootDisabled(s));
+    }
+    Ok(())
+}
+
+/// Post-convergence paths from S to D once the failure has happened. Equal
+/// cost paths can be many on meshed topologies, `tilfa_failure()` walks the
+/// post-convergence DAG lazily instead.
+pub fn pc_paths(
+    graph: &Graph,
+    s: usize,
+    d: usize,
+    failure: &Failure,
+) -> Result<Vec<Vec<usize>>, SpfError> {
+    check_ends(graph, s, d, failure)?;
+
+    let paths = spf_dag_with(graph, s, &SpfDirect::Normal, failure)
+        .paths(d)
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        return Err(SpfError::NoRepairPath(d));
     }
+    Ok(paths)
+}
 
-    spf(&pc_graph, s, &SpfOpt::default())
-        .remove(&d)
-        .map_or_else(Vec::new, |data| data.paths)
+// S, its first hop and D of a post-convergence path, which must hold at
+// least S and one more node.
+pub(crate) fn pc_path_ends(pc_path: &[usize]) -> Result<(usize, usize, usize), SpfError> {
+    match pc_path {
+        [s, nexthop, .., d] | [s, nexthop @ d] => Ok((*s, *nexthop, *d)),
+        [s] => Err(SpfError::InvalidPath(*s)),
+        [] => Err(SpfError::EmptyPath),
+    }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Intersect {
     pub id: usize,
     pub p: bool,
@@ -293,7 +719,7 @@ pub struct Intersect {
 
 // Intersect with P and Q.
 pub fn intersect(
-    pc_path: &Vec<usize>,
+    pc_path: &[usize],
     p_nodes: &HashSet<usize>,
     q_nodes: &HashSet<usize>,
 ) -> Vec<Intersect> {
@@ -311,13 +737,17 @@ pub fn intersect(
     intersects
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SrSegment {
     NodeSid(usize),
     AdjSid(usize, usize),
 }
 
-pub fn make_repair_list(pc_inter: &[Intersect], s: usize, d: usize) -> Vec<SrSegment> {
+pub fn make_repair_list(
+    pc_inter: &[Intersect],
+    s: usize,
+    d: usize,
+) -> Result<Vec<SrSegment>, SpfError> {
     let mut sr_segments = Vec::new();
 
     let mut prev_id = None;
@@ -325,26 +755,38 @@ pub fn make_repair_list(pc_inter: &[Intersect], s: usize, d: usize) -> Vec<SrSeg
     let mut q_mode = false;
 
     for (index, inter) in pc_inter.iter().enumerate() {
+        if inter.id == s || inter.id == d {
+            return Err(SpfError::InvalidPath(inter.id));
+        }
+
         if index == 0 {
-            if inter.p {
+            if inter.p && inter.q {
+                // Plain LFA, the neighbor itself does not go through the
+                // failure.
+                return Ok(Vec::new());
+            } else if inter.p {
                 p_mode = true;
             } else {
                 sr_segments.push(SrSegment::AdjSid(s, inter.id));
             }
         } else if p_mode {
-            if !inter.p {
+            if inter.p && inter.q {
+                // PQ node, release the packet right there.
+                sr_segments.push(SrSegment::NodeSid(inter.id));
+                p_mode = false;
+            } else if !inter.p {
                 if let Some(prev_id) = prev_id {
                     sr_segments.push(SrSegment::NodeSid(prev_id));
-                }
-                if !q_mode {
-                    sr_segments.push(SrSegment::AdjSid(prev_id.unwrap(), inter.id));
+                    if !q_mode {
+                        sr_segments.push(SrSegment::AdjSid(prev_id, inter.id));
+                    }
                 }
                 p_mode = false;
             }
-        } else if let Some(prev_id) = prev_id {
-            if !q_mode {
-                sr_segments.push(SrSegment::AdjSid(prev_id, inter.id));
-            }
+        } else if let Some(prev_id) = prev_id
+            && !q_mode
+        {
+            sr_segments.push(SrSegment::AdjSid(prev_id, inter.id));
         }
 
         if inter.q {
@@ -353,70 +795,393 @@ pub fn make_repair_list(pc_inter: &[Intersect], s: usize, d: usize) -> Vec<SrSeg
         prev_id = Some(inter.id);
     }
 
-    if !q_mode {
-        if let Some(prev_id) = prev_id {
-            sr_segments.push(SrSegment::AdjSid(prev_id, d));
+    if !q_mode && let Some(prev_id) = prev_id {
+        // The whole path is in P space, steer to its end first.
+        if p_mode {
+            sr_segments.push(SrSegment::NodeSid(prev_id));
         }
+        sr_segments.push(SrSegment::AdjSid(prev_id, d));
     }
 
-    sr_segments
+    Ok(sr_segments)
+}
+
+// Node name for display, the id itself when the node is unknown.
+pub(crate) fn node_name(graph: &Graph, id: usize) -> String {
+    graph
+        .get(&id)
+        .map_or_else(|| id.to_string(), |n| n.name.clone())
 }
 
-pub fn repair_list_print(graph: &Graph, repair_list: &Vec<SrSegment>) {
+pub fn repair_list_print(graph: &Graph, repair_list: &[SrSegment]) {
     for list in repair_list {
         match list {
             SrSegment::NodeSid(nid) => {
-                print!("NodeSid({}) ", graph.get(nid).map(|n| &n.name).unwrap());
+                print!("NodeSid({}) ", node_name(graph, *nid));
             }
             SrSegment::AdjSid(from, to) => {
                 print!(
                     "AdjSid({}, {}) ",
-                    graph.get(from).map(|n| &n.name).unwrap(),
-                    graph.get(to).map(|n| &n.name).unwrap()
+                    node_name(graph, *from),
+                    node_name(graph, *to)
                 );
             }
         }
     }
 }
 
-pub fn tilfa(graph: &Graph, s: usize, d: usize, x: usize) {
-    let p_nodes = p_space_nodes(graph, s, x);
-    let q_nodes = q_space_nodes(graph, d, x);
-    let mut pc_paths = pc_paths(graph, s, d, x);
+#[derive(Default)]
+pub struct TilfaOpt {
+    /// Use the extended P space (RFC 7490): nodes in the P space of the
+    /// first hop of a post-convergence path are release points as well,
+    /// reached over the adjacency of S to that first hop.
+    pub extended_p_space: bool,
+    /// Element protected by `tilfa()`. Node protection falls back to link
+    /// protection when D is X or no usable repair path avoids X.
+    pub protection: Protection,
+    /// Only use nodes supporting SR-MPLS as release points and Adj-SID
+    /// endpoints.
+    pub srmpls: bool,
+    /// Only use nodes supporting SRv6 as release points and Adj-SID
+    /// endpoints.
+    pub srv6: bool,
+    /// Always minimize repair lists, not only those exceeding the MSD of S.
+    pub minimize: bool,
+    /// Repair every post-convergence path instead of stopping at the first
+    /// one with a usable repair list. Their number grows exponentially on
+    /// meshed topologies.
+    pub all_paths: bool,
+}
+
+impl TilfaOpt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+// The dataplane selection of the SPF options.
+impl From<&SpfOpt> for TilfaOpt {
+    fn from(opt: &SpfOpt) -> Self {
+        Self {
+            srmpls: opt.srmpls,
+            srv6: opt.srv6,
+            ..Default::default()
+        }
+    }
+}
+
+/// Repair computed for one post-convergence path.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TilfaPath {
+    /// Post-convergence path from S to D, both ends included.
+    pub pc_path: Vec<usize>,
+    /// P/Q membership of each intermediate node of `pc_path`.
+    pub intersects: Vec<Intersect>,
+    pub repair_list: Vec<SrSegment>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TilfaResult {
+    /// Failure the repair paths protect against.
+    pub failure: Failure,
+    pub p_nodes: HashSet<usize>,
+    pub q_nodes: HashSet<usize>,
+    pub paths: Vec<TilfaPath>,
+}
+
+pub fn tilfa(
+    graph: &Graph,
+    s: usize,
+    d: usize,
+    x: usize,
+    opt: &TilfaOpt,
+) -> Result<TilfaResult, SpfError> {
+    if !graph.contains_key(&x) {
+        return Err(SpfError::UnknownNode(x));
+    }
+    let failure = if opt.protection == Protection::Srlg {
+        Failure::srlg(graph, s, x)
+    } else {
+        Failure::link(graph, s, x)
+    };
+    if failure.links.is_empty() {
+        return Err(SpfError::NotAdjacent(s, x));
+    }
+    if opt.protection == Protection::Node && d != x {
+        match tilfa_failure(graph, s, d, &Failure::node(x), opt) {
+            Err(err) if err.is_unprotectable() => {}
+            result => return result,
+        }
+    }
+    tilfa_failure(graph, s, d, &failure, opt)
+}
+
+// Whether the node supports the dataplanes selected in `opt`.
+fn is_sr_capable(graph: &Graph, id: usize, opt: &TilfaOpt) -> bool {
+    graph.get(&id).is_some_and(|node| {
+        (!opt.srmpls || node.is_srmpls()) && (!opt.srv6 || node.is_srv6_capable())
+    })
+}
 
+// Every node processing a segment of the repair list, S included, must
+// support the selected dataplanes.
+fn check_repair_list(
+    graph: &Graph,
+    s: usize,
+    repair_list: &[SrSegment],
+    opt: &TilfaOpt,
+) -> Result<(), SpfError> {
+    if repair_list.is_empty() {
+        return Ok(());
+    }
+    if !is_sr_capable(graph, s, opt) {
+        return Err(SpfError::NotSrCapable(s));
+    }
+    for segment in repair_list {
+        let id = match segment {
+            SrSegment::NodeSid(id) => *id,
+            SrSegment::AdjSid(from, _) => *from,
+        };
+        if !is_sr_capable(graph, id, opt) {
+            return Err(SpfError::NotSrCapable(id));
+        }
+    }
+    Ok(())
+}
+
+/// Number of SIDs S pushes for `repair_list`. A leading Adj-SID of S only
+/// selects the outgoing adjacency and is not counted.
+pub fn repair_list_depth(s: usize, repair_list: &[SrSegment]) -> usize {
+    match repair_list.first() {
+        Some(SrSegment::AdjSid(from, _)) if *from == s => repair_list.len() - 1,
+        _ => repair_list.len(),
+    }
+}
+
+/// Repair list for `pc_path` with as few segments as possible. S forwards
+/// to the first hop of `pc_path` itself. From there each Node-SID steers
+/// the packet to the farthest node of `pc_path` whose pre-convergence
+/// shortest paths from the previous segment endpoint all avoid the
+/// failure, and an Adj-SID is only used when there is no such node.
+pub fn minimize_repair_list(
+    graph: &Graph,
+    pc_path: &[usize],
+    failure: &Failure,
+    opt: &TilfaOpt,
+) -> Result<Vec<SrSegment>, SpfError> {
+    let (s, nexthop, _) = pc_path_ends(pc_path)?;
+    let last = pc_path.len() - 1;
+
+    let mut segments = Vec::new();
+    let mut anchor = 1;
+
+    while anchor < last {
+        let id = pc_path[anchor];
+        let p_nodes = p_space_nodes(graph, id, failure);
+        let reach = (anchor + 1..=last).rev().find(|index| {
+            let target = pc_path[*index];
+            p_nodes.contains(&target) && (*index == last || is_sr_capable(graph, target, opt))
+        });
+        match reach {
+            Some(index) if index == last => break,
+            Some(index) => {
+                segments.push(SrSegment::NodeSid(pc_path[index]));
+                anchor = index;
+            }
+            None => {
+                segments.push(SrSegment::AdjSid(id, pc_path[anchor + 1]));
+                anchor += 1;
+            }
+        }
+    }
+
+    // The adjacency to the first hop is not a shortest path of S.
+    if !segments.is_empty() && !p_space_nodes(graph, s, failure).contains(&nexthop) {
+        segments.insert(0, SrSegment::AdjSid(s, nexthop));
+    }
+
+    Ok(segments)
+}
+
+/// TI-LFA against an arbitrary failure, e.g. one of several parallel links.
+/// Nodes not supporting the dataplanes selected in `opt` are left out of
+/// the P space. Repair lists deeper than the MSD of S are minimized.
+/// Post-convergence paths whose repair list still needs such a node or
+/// exceeds the MSD are dropped, and the error is returned when none is left.
+/// Only the first usable path is kept unless `opt.all_paths` is set.
+pub fn tilfa_failure(
+    graph: &Graph,
+    s: usize,
+    d: usize,
+    failure: &Failure,
+    opt: &TilfaOpt,
+) -> Result<TilfaResult, SpfError> {
+    check_ends(graph, s, d, failure)?;
+    let dag = spf_dag(graph, s, &SpfDirect::Normal);
+    if !dag.nodes.contains_key(&d) {
+        return Err(SpfError::Unreachable(d));
+    }
+
+    let mut source = TilfaSource::new(graph, &dag, failure.clone(), opt);
+    let q_nodes = q_space_nodes(graph, d, failure);
+    let paths = source.repair(graph, d, &q_nodes, opt)?;
+
+    let mut p_nodes = source.p_nodes;
+    for nodes in source.extended.into_values() {
+        p_nodes.extend(nodes);
+    }
+
+    Ok(TilfaResult {
+        failure: source.failure,
+        p_nodes,
+        q_nodes,
+        paths,
+    })
+}
+
+// Part of `tilfa_failure()` which only depends on S and the failure, so
+// that it is shared by every D.
+pub(crate) struct TilfaSource {
+    s: usize,
+    pub(crate) failure: Failure,
+    // P space of S, release points only.
+    p_nodes: HashSet<usize>,
+    pc_dag: SpfDag,
+    // Extended P space of each first hop tried: S forwards over the
+    // adjacency to it, so only the P space of that very neighbor is usable.
+    extended: HashMap<usize, HashSet<usize>>,
+}
+
+impl TilfaSource {
+    // `dag` is the pre-convergence SPF DAG of S.
+    pub(crate) fn new(graph: &Graph, dag: &SpfDag, failure: Failure, opt: &TilfaOpt) -> Self {
+        let mut p_nodes = space_nodes(graph, dag, &failure, &SpfDirect::Normal);
+        p_nodes.retain(|id| is_sr_capable(graph, *id, opt));
+        let pc_dag = spf_dag_with(graph, dag.root, &SpfDirect::Normal, &failure);
+        Self {
+            s: dag.root,
+            failure,
+            p_nodes,
+            pc_dag,
+            extended: HashMap::new(),
+        }
+    }
+
+    // Repairs of the post-convergence paths toward D, whose Q space is
+    // `q_nodes`.
+    pub(crate) fn repair(
+        &mut self,
+        graph: &Graph,
+        d: usize,
+        q_nodes: &HashSet<usize>,
+        opt: &TilfaOpt,
+    ) -> Result<Vec<TilfaPath>, SpfError> {
+        let s = self.s;
+        let msd = graph.get(&s).and_then(|node| node.msd);
+        let mut path_err = None;
+        let mut paths = Vec::new();
+
+        for pc_path in self.pc_dag.paths(d) {
+            // Remove S and D.
+            let inner = if pc_path.len() >= 2 {
+                &pc_path[1..pc_path.len() - 1]
+            } else {
+                &[]
+            };
+
+            let nexthop = inner.first().copied();
+            let path_p_nodes = match nexthop {
+                Some(nexthop) if opt.extended_p_space => {
+                    self.extended.entry(nexthop).or_insert_with(|| {
+                        let mut nodes = p_space_nodes(graph, nexthop, &self.failure);
+                        nodes.insert(nexthop);
+                        nodes.remove(&s);
+                        nodes.retain(|id| is_sr_capable(graph, *id, opt));
+                        nodes.extend(self.p_nodes.iter().copied());
+                        nodes
+                    })
+                }
+                _ => &self.p_nodes,
+            };
+
+            // Intersect
+            let intersects = intersect(inner, path_p_nodes, q_nodes);
+
+            // Convert PC intersects into repair list.
+            let mut repair_list = make_repair_list(&intersects, s, d)?;
+
+            // A release point outside the P space of S is reached through
+            // the first hop, steer the packet there first.
+            if let (Some(nexthop), Some(SrSegment::NodeSid(id))) = (nexthop, repair_list.first())
+                && !self.p_nodes.contains(id)
+            {
+                if *id == nexthop {
+                    repair_list[0] = SrSegment::AdjSid(s, nexthop);
+                } else {
+                    repair_list.insert(0, SrSegment::AdjSid(s, nexthop));
+                }
+            }
+
+            let depth = repair_list_depth(s, &repair_list);
+            if opt.minimize || msd.is_some_and(|msd| depth > msd) {
+                let minimized = minimize_repair_list(graph, &pc_path, &self.failure, opt)?;
+                if repair_list_depth(s, &minimized) < depth {
+                    repair_list = minimized;
+                }
+            }
+
+            if let Err(err) = check_repair_list(graph, s, &repair_list, opt) {
+                path_err.get_or_insert(err);
+                continue;
+            }
+            if msd.is_some_and(|msd| repair_list_depth(s, &repair_list) > msd) {
+                path_err.get_or_insert(SpfError::MsdExceeded(s));
+                continue;
+            }
+
+            paths.push(TilfaPath {
+                pc_path,
+                intersects,
+                repair_list,
+            });
+            if !opt.all_paths {
+                break;
+            }
+        }
+
+        if paths.is_empty() {
+            return Err(path_err.unwrap_or(SpfError::NoRepairPath(d)));
+        }
+        Ok(paths)
+    }
+}
+
+pub fn tilfa_print(graph: &Graph, result: &TilfaResult) {
     // P
     print!("P:");
-    for name in p_nodes.iter().filter_map(|p| graph.get(p).map(|n| &n.name)) {
-        print!(" {}", name);
+    for p in &result.p_nodes {
+        print!(" {}", node_name(graph, *p));
     }
     println!();
 
     // Q
     print!("Q:");
-    for name in q_nodes.iter().filter_map(|q| graph.get(q).map(|n| &n.name)) {
-        print!(" {}", name);
+    for q in &result.q_nodes {
+        print!(" {}", node_name(graph, *q));
     }
     println!();
 
-    // PCPath.
-    for path in &mut pc_paths {
-        // Remove S and D.
-        path.remove(0);
-        path.pop();
-
+    for path in &result.paths {
         // Display PCPath.
         print!("PCPath:");
-        for name in path.iter().filter_map(|q| graph.get(q).map(|n| &n.name)) {
-            print!(" {}", name);
+        for inter in &path.intersects {
+            print!(" {}", node_name(graph, inter.id));
         }
         println!();
 
-        // Intersect
-        let pc_inter = intersect(path, &p_nodes, &q_nodes);
-
         // Display PCPath & P intersect.
         print!("Pinter:");
-        for inter in &pc_inter {
+        for inter in &path.intersects {
             if inter.p {
                 print!(" o ");
             } else {
@@ -427,7 +1192,7 @@ pub fn tilfa(graph: &Graph, s: usize, d: usize, x: usize) {
 
         // Display PCPath & Q intersect.
         print!("Qinter:");
-        for inter in &pc_inter {
+        for inter in &path.intersects {
             if inter.q {
                 print!(" o ");
             } else {
@@ -436,10 +1201,8 @@ pub fn tilfa(graph: &Graph, s: usize, d: usize, x: usize) {
         }
         println!();
 
-        // Convert PC intersects into repair list.
-        let repair_list = make_repair_list(&pc_inter, s, d);
-        repair_list_print(graph, &repair_list);
-        //println!("{:?}", repair_list);
+        repair_list_print(graph, &path.repair_list);
+        println!();
     }
 }
 
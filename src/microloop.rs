@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::{
+    Graph, Intersect, SpfDag, SpfDirect, SpfError, SrSegment, intersect, make_repair_list,
+    node_name, repair_list_depth, repair_list_print, spf_dag,
+};
+
+/// Destination of S exposed to a microloop while the network converges
+/// from one topology to another.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Microloop {
+    /// Loop of old and new nexthops reachable from S, each node forwarding
+    /// to the next one and the last one back to the first.
+    pub loop_nodes: Vec<usize>,
+    /// Post-convergence path from S to D, both ends included.
+    pub pc_path: Vec<usize>,
+    /// Stability of each intermediate node of `pc_path`. `p` when the
+    /// shortest paths from S to the node are the same in both topologies,
+    /// `q` when those from the node to D are.
+    pub intersects: Vec<Intersect>,
+    /// Segments S pushes to keep D's traffic on `pc_path` until every node
+    /// has converged.
+    pub segment_list: Vec<SrSegment>,
+}
+
+// Post-convergence paths compared per destination, their number grows
+// exponentially on meshed topologies.
+const PC_PATH_MAX: usize = 64;
+
+// First loop found walking the union of the nexthops toward D from S. The
+// nexthops of a node are its parents in the reverse SPF DAG of D.
+fn find_loop(s: usize, old: &SpfDag, new: &SpfDag) -> Option<Vec<usize>> {
+    let next = |id: usize| -> Vec<usize> {
+        let mut next = BTreeSet::new();
+        for dag in [old, new] {
+            if let Some(node) = dag.nodes.get(&id) {
+                next.extend(node.parents.iter().copied());
+            }
+        }
+        next.into_iter().collect()
+    };
+
+    // Depth first with an explicit stack of (node, successors, index) so
+    // long paths do not exhaust the thread stack.
+    let mut done = HashSet::new();
+    let mut on_stack = HashSet::from([s]);
+    let mut stack = vec![(s, next(s), 0)];
+
+    while let Some((id, succ, index)) = stack.last_mut() {
+        let Some(to) = succ.get(*index).copied() else {
+            done.insert(*id);
+            on_stack.remove(id);
+            stack.pop();
+            continue;
+        };
+        *index += 1;
+
+        if on_stack.contains(&to) {
+            let start = stack.iter().position(|(id, _, _)| *id == to)?;
+            return Some(stack[start..].iter().map(|(id, _, _)| *id).collect());
+        }
+        if !done.contains(&to) {
+            on_stack.insert(to);
+            stack.push((to, next(to), 0));
+        }
+    }
+    None
+}
+
+// Nodes whose shortest paths from the root are the same in both SPF DAGs,
+// the root excepted. That holds when a node has the same cost and parents
+// in both and all its parents hold it too, which one pass in cost order
+// decides.
+fn stable_nodes(old: &SpfDag, new: &SpfDag) -> HashSet<usize> {
+    let mut nodes = new
+        .nodes
+        .iter()
+        .map(|(id, node)| (node.cost, *id))
+        .collect::<Vec<_>>();
+    nodes.sort_unstable();
+
+    let mut stable = HashSet::from([new.root]);
+    for (cost, id) in nodes {
+        let node = &new.nodes[&id];
+        if id == new.root || node.parents.is_empty() {
+            continue;
+        }
+        let Some(old_node) = old.nodes.get(&id) else {
+            continue;
+        };
+        let parents = node.parents.iter().collect::<BTreeSet<_>>();
+        if old_node.cost == cost
+            && old_node.parents.iter().collect::<BTreeSet<_>>() == parents
+            && parents.iter().all(|parent| stable.contains(*parent))
+        {
+            stable.insert(id);
+        }
+    }
+    stable.remove(&new.root);
+    stable
+}
+
+/// Finds the destinations of S exposed to microloops when the network
+/// converges from `old` to `new`, a loop being possible as soon as the old
+/// and new nexthops toward D form a cycle reachable from S. For each of
+/// them computes the segment list keeping the traffic of S on the
+/// post-convergence path whichever nodes already converged. Segments only
+/// target nodes whose shortest paths from S (P) or to D (Q) did not change,
+/// the same way `make_repair_list()` builds a TI-LFA repair list, and the
+/// post-convergence path needing the fewest SIDs among the first
+/// `PC_PATH_MAX` ones is chosen.
+pub fn microloop_calc(
+    old: &Graph,
+    new: &Graph,
+    s: usize,
+) -> Result<BTreeMap<usize, Microloop>, SpfError> {
+    let Some(node) = new.get(&s) else {
+        return Err(SpfError::UnknownNode(s));
+    };
+    if node.is_disabled {
+        return Err(SpfError::RootDisabled(s));
+    }
+
+    let old_dag = spf_dag(old, s, &SpfDirect::Normal);
+    let new_dag = spf_dag(new, s, &SpfDirect::Normal);
+    let p_nodes = stable_nodes(&old_dag, &new_dag);
+
+    let mut microloops = BTreeMap::new();
+
+    for d in new_dag.nodes.keys() {
+        if *d == s {
+            continue;
+        }
+        let old_rdag = spf_dag(old, *d, &SpfDirect::Reverse);
+        let new_rdag = spf_dag(new, *d, &SpfDirect::Reverse);
+
+        let Some(loop_nodes) = find_loop(s, &old_rdag, &new_rdag) else {
+            continue;
+        };
+        let q_nodes = stable_nodes(&old_rdag, &new_rdag);
+
+        let mut best: Option<Microloop> = None;
+        for pc_path in new_dag.paths(*d).take(PC_PATH_MAX) {
+            let inner = if pc_path.len() >= 2 {
+                &pc_path[1..pc_path.len() - 1]
+            } else {
+                &[]
+            };
+            let intersects = intersect(inner, &p_nodes, &q_nodes);
+            let segment_list = make_repair_list(&intersects, s, *d)?;
+            let depth = repair_list_depth(s, &segment_list);
+
+            if best
+                .as_ref()
+                .is_none_or(|best| depth < repair_list_depth(s, &best.segment_list))
+            {
+                best = Some(Microloop {
+                    loop_nodes: loop_nodes.clone(),
+                    pc_path,
+                    intersects,
+                    segment_list,
+                });
+            }
+            // No other path can do better.
+            if depth == 0 {
+                break;
+            }
+        }
+        if let Some(best) = best {
+            microloops.insert(*d, best);
+        }
+    }
+    Ok(microloops)
+}
+
+pub fn microloop_print(graph: &Graph, microloops: &BTreeMap<usize, Microloop>) {
+    for (d, microloop) in microloops {
+        print!("{} loop:", node_name(graph, *d));
+        for id in &microloop.loop_nodes {
+            print!(" {}", node_name(graph, *id));
+        }
+        print!(" path:");
+        for id in &microloop.pc_path {
+            print!(" {}", node_name(graph, *id));
+        }
+        print!(" segments: ");
+        repair_list_print(graph, &microloop.segment_list);
+        println!();
+    }
+}
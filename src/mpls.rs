@@ -0,0 +1,116 @@
+use crate::{Graph, SpfError, SrSegment, pc_path_ends};
+
+pub const MPLS_LABEL_IPV4_EXPLICIT_NULL: u32 = 0;
+pub const MPLS_LABEL_IMPLICIT_NULL: u32 = 3;
+
+/// SR-MPLS global block of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Srgb {
+    pub base: u32,
+    pub range: u32,
+}
+
+impl Srgb {
+    pub fn new(base: u32, range: u32) -> Self {
+        Self { base, range }
+    }
+
+    /// Label of Prefix-SID `index` in this block.
+    pub fn label(&self, index: u32) -> Option<u32> {
+        if index < self.range {
+            self.base.checked_add(index)
+        } else {
+            None
+        }
+    }
+}
+
+/// Labels pushed by S toward `nexthop`, top of the stack first.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LabelStack {
+    pub nexthop: usize,
+    pub labels: Vec<u32>,
+}
+
+// Label of `target`'s Prefix-SID as read by `reader`. When the reader is
+// the target itself, the previous hop applies the penultimate hop popping
+// rules and the label is either omitted or replaced by explicit-null.
+fn node_label(graph: &Graph, reader: usize, target: usize) -> Result<Option<u32>, SpfError> {
+    let node = graph.get(&target).ok_or(SpfError::UnknownNode(target))?;
+    if reader == target {
+        if node.explicit_null {
+            return Ok(Some(MPLS_LABEL_IPV4_EXPLICIT_NULL));
+        }
+        if !node.no_php {
+            return Ok(None);
+        }
+    }
+    let index = node.prefix_sid.ok_or(SpfError::NoPrefixSid(target))?;
+    let srgb = graph
+        .get(&reader)
+        .ok_or(SpfError::UnknownNode(reader))?
+        .srgb
+        .ok_or(SpfError::NoPrefixSid(target))?;
+    srgb.label(index)
+        .map(Some)
+        .ok_or(SpfError::NoPrefixSid(target))
+}
+
+fn adj_label(graph: &Graph, from: usize, to: usize) -> Result<u32, SpfError> {
+    graph
+        .get(&from)
+        .ok_or(SpfError::UnknownNode(from))?
+        .olinks
+        .iter()
+        .filter(|link| link.to == to && link.is_active())
+        .find_map(|link| link.adj_sid)
+        .ok_or(SpfError::NoAdjSid(from, to))
+}
+
+/// Turns the repair list of a post-convergence path into the label stack S
+/// pushes. S sends the packet to the first hop of `pc_path`, so the top
+/// label is resolved in the SRGB of that neighbor, and each following
+/// Node-SID label in the SRGB of the node where the previous segment ends.
+/// An Adj-SID of S itself is not pushed, it selects the outgoing
+/// adjacency. The stack ends with the Prefix-SID of D.
+pub fn mpls_label_stack(
+    graph: &Graph,
+    pc_path: &[usize],
+    repair_list: &[SrSegment],
+) -> Result<LabelStack, SpfError> {
+    let (s, nexthop, d) = pc_path_ends(pc_path)?;
+
+    let mut segments = repair_list;
+    let mut reader = nexthop;
+
+    if let Some(SrSegment::AdjSid(from, to)) = segments.first()
+        && *from == s
+    {
+        if *to != nexthop {
+            return Err(SpfError::InvalidPath(*to));
+        }
+        segments = &segments[1..];
+    }
+
+    let mut labels = Vec::new();
+
+    for segment in segments.iter().chain([SrSegment::NodeSid(d)].iter()) {
+        match segment {
+            SrSegment::NodeSid(id) => {
+                if let Some(label) = node_label(graph, reader, *id)? {
+                    labels.push(label);
+                }
+                reader = *id;
+            }
+            SrSegment::AdjSid(from, to) => {
+                if *from != reader {
+                    return Err(SpfError::InvalidPath(*from));
+                }
+                labels.push(adj_label(graph, *from, *to)?);
+                reader = *to;
+            }
+        }
+    }
+
+    Ok(LabelStack { nexthop, labels })
+}
@@ -0,0 +1,174 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::lfa::{distance, neighbors, primaries};
+use crate::{
+    Failure, Graph, Path, SpfOpt, extended_p_space_nodes, p_space_nodes, q_space_nodes, spf,
+};
+
+/// Remote LFA of S for one destination over the protected adjacency S-E
+/// (RFC 7490).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rlfa {
+    /// Primary nexthop E whose adjacency is protected.
+    pub primary: usize,
+    /// PQ node terminating the repair tunnel.
+    pub pq_node: usize,
+    /// Neighbor of S the tunnel is sent to.
+    pub nexthop: usize,
+    /// Cost of the repair path from S to D through the PQ node.
+    pub cost: u32,
+}
+
+// PQ node candidate of one protected adjacency.
+#[derive(Debug, Clone)]
+struct PqNode {
+    id: usize,
+    nexthop: usize,
+    tunnel_cost: u32,
+}
+
+// PQ nodes of the protected adjacency S-E, each with the neighbor of S
+// whose P space holds it. The cheapest tunnel wins, then the lowest
+// neighbor id.
+fn pq_candidates(
+    graph: &Graph,
+    s: usize,
+    e: usize,
+    nbr_cost: &BTreeMap<usize, u32>,
+    nbr_spf: &BTreeMap<usize, BTreeMap<usize, Path>>,
+) -> Vec<PqNode> {
+    let failure = Failure::link(graph, s, e);
+
+    let mut q_nodes = q_space_nodes(graph, e, &failure);
+    q_nodes.insert(e);
+
+    let mut pq_nodes = extended_p_space_nodes(graph, s, &failure)
+        .into_iter()
+        .filter(|id| q_nodes.contains(id))
+        .collect::<Vec<_>>();
+    pq_nodes.sort_unstable();
+
+    let mut nbr_p_nodes = BTreeMap::<usize, HashSet<usize>>::new();
+    let mut pq = Vec::new();
+
+    for id in pq_nodes {
+        let mut best: Option<PqNode> = None;
+        for (n, cost) in nbr_cost {
+            if *n == e {
+                continue;
+            }
+            let Some(n_pq) = nbr_spf.get(n).and_then(|spf| distance(spf, id)) else {
+                continue;
+            };
+            if *n != id
+                && !nbr_p_nodes
+                    .entry(*n)
+                    .or_insert_with(|| p_space_nodes(graph, *n, &failure))
+                    .contains(&id)
+            {
+                continue;
+            }
+            let candidate = PqNode {
+                id,
+                nexthop: *n,
+                tunnel_cost: cost.saturating_add(n_pq),
+            };
+            if best
+                .as_ref()
+                .is_none_or(|best| candidate.tunnel_cost < best.tunnel_cost)
+            {
+                best = Some(candidate);
+            }
+        }
+        pq.extend(best);
+    }
+    pq
+}
+
+/// PQ nodes of S for the protected adjacency S-E: the intersection of the
+/// extended P space of S and the Q space of E, both with respect to the
+/// S-E link.
+pub fn pq_nodes(graph: &Graph, s: usize, e: usize) -> BTreeSet<usize> {
+    let opt = SpfOpt::default();
+    let nbr_cost = neighbors(graph, s);
+    let nbr_spf = nbr_cost
+        .keys()
+        .map(|n| (*n, spf(graph, *n, &opt)))
+        .collect::<BTreeMap<_, _>>();
+
+    pq_candidates(graph, s, e, &nbr_cost, &nbr_spf)
+        .into_iter()
+        .map(|pq| pq.id)
+        .collect()
+}
+
+/// Computes the remote LFA of S for every destination and each of its
+/// primary nexthops. A PQ node qualifies for D when none of its shortest
+/// paths to D go back over the protected link. Among qualifying PQ nodes
+/// the one with the cheapest repair path wins, then the one closest to S,
+/// then the lowest node id.
+pub fn rlfa(graph: &Graph, s: usize) -> BTreeMap<usize, Vec<Rlfa>> {
+    let opt = SpfOpt::default();
+    let root_spf = spf(graph, s, &opt);
+    let nbr_cost = neighbors(graph, s);
+    let nbr_spf = nbr_cost
+        .keys()
+        .map(|n| (*n, spf(graph, *n, &opt)))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut pq_cache = BTreeMap::<usize, Vec<PqNode>>::new();
+    let mut pq_spf = BTreeMap::<usize, BTreeMap<usize, Path>>::new();
+    let mut rlfas = BTreeMap::new();
+
+    for (d, path) in &root_spf {
+        if *d == s {
+            continue;
+        }
+        let mut repairs = Vec::new();
+
+        for e in primaries(&nbr_cost, &nbr_spf, *d, path.cost) {
+            let Some(e_d) = nbr_spf.get(&e).and_then(|spf| distance(spf, *d)) else {
+                continue;
+            };
+            let s_e = nbr_cost[&e];
+
+            let candidates = pq_cache
+                .entry(e)
+                .or_insert_with(|| pq_candidates(graph, s, e, &nbr_cost, &nbr_spf));
+
+            let mut best: Option<(u32, u32, usize, usize)> = None;
+
+            for pq in candidates.iter() {
+                let spf = pq_spf
+                    .entry(pq.id)
+                    .or_insert_with(|| spf(graph, pq.id, &opt));
+                let (Some(pq_d), Some(pq_s)) = (distance(spf, *d), distance(spf, s)) else {
+                    continue;
+                };
+                if pq_s.saturating_add(s_e).saturating_add(e_d) <= pq_d {
+                    continue;
+                }
+                let key = (
+                    pq.tunnel_cost.saturating_add(pq_d),
+                    pq.tunnel_cost,
+                    pq.id,
+                    pq.nexthop,
+                );
+                if best.is_none_or(|best| key < best) {
+                    best = Some(key);
+                }
+            }
+
+            if let Some((cost, _, pq_node, nexthop)) = best {
+                repairs.push(Rlfa {
+                    primary: e,
+                    pq_node,
+                    nexthop,
+                    cost,
+                });
+            }
+        }
+        rlfas.insert(*d, repairs);
+    }
+    rlfas
+}
@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::{Graph, Path, node_name};
+
+/// IPv4 or IPv6 prefix. Host bits are cleared on creation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Prefix {
+    pub addr: IpAddr,
+    pub len: u8,
+}
+
+impl Prefix {
+    pub fn new(addr: IpAddr, len: u8) -> Self {
+        let (addr, len) = match addr {
+            IpAddr::V4(addr) => {
+                let len = len.min(32);
+                let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+                (IpAddr::V4(Ipv4Addr::from(u32::from(addr) & mask)), len)
+            }
+            IpAddr::V6(addr) => {
+                let len = len.min(128);
+                let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+                (IpAddr::V6(Ipv6Addr::from(u128::from(addr) & mask)), len)
+            }
+        };
+        Self { addr, len }
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+/// Route to a prefix through the advertising nodes closest to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// SPF cost to the advertising node plus the prefix metric.
+    pub cost: u32,
+    /// Advertising nodes at that cost, more than one for an anycast prefix.
+    pub nodes: BTreeSet<usize>,
+    /// First hops toward any of the nodes, empty when the root advertises
+    /// the prefix itself.
+    pub nexthops: BTreeSet<usize>,
+}
+
+/// Routes added, changed and withdrawn by a route calculation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RouteDiff {
+    pub added: BTreeMap<Prefix, Route>,
+    pub changed: BTreeMap<Prefix, Route>,
+    pub removed: BTreeSet<Prefix>,
+}
+
+impl RouteDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+// First hops of the shortest paths, whether SPF ran in full path mode or
+// not.
+fn path_nexthops(path: &Path) -> BTreeSet<usize> {
+    path.paths
+        .iter()
+        .chain(path.nexthops.iter())
+        .filter_map(|p| p.get(1).copied())
+        .collect()
+}
+
+// Best route to each prefix accepted by `filter`.
+fn routes(
+    graph: &Graph,
+    spf: &BTreeMap<usize, Path>,
+    filter: impl Fn(&Prefix) -> bool,
+) -> BTreeMap<Prefix, Route> {
+    let mut routes = BTreeMap::<Prefix, Route>::new();
+
+    for (id, node) in graph {
+        let Some(path) = spf.get(id) else {
+            continue;
+        };
+        for (prefix, metric) in &node.prefixes {
+            if !filter(prefix) {
+                continue;
+            }
+            let cost = path.cost.saturating_add(*metric);
+            let route = routes.entry(*prefix).or_insert_with(|| Route {
+                cost,
+                nodes: BTreeSet::new(),
+                nexthops: BTreeSet::new(),
+            });
+            if cost < route.cost {
+                route.cost = cost;
+                route.nodes.clear();
+                route.nexthops.clear();
+            }
+            if cost == route.cost {
+                route.nodes.insert(*id);
+                route.nexthops.extend(path_nexthops(path));
+            }
+        }
+    }
+
+    // The root is the only node reached without a first hop.
+    let root = spf
+        .iter()
+        .find(|(_, path)| path.cost == 0 && path_nexthops(path).is_empty())
+        .map(|(id, _)| *id);
+    if let Some(root) = root {
+        for route in routes.values_mut() {
+            if route.nodes.contains(&root) {
+                route.nexthops.clear();
+            }
+        }
+    }
+    routes
+}
+
+/// Routes to every prefix advertised by a node reachable in `spf`. A
+/// prefix advertised by several nodes goes to those with the lowest cost,
+/// with the nexthops toward all of them. When the root is one of them the
+/// route is local and has no nexthop.
+pub fn route_calc(graph: &Graph, spf: &BTreeMap<usize, Path>) -> BTreeMap<Prefix, Route> {
+    routes(graph, spf, |_| true)
+}
+
+/// Partial route calculation: after the advertisement of `prefixes`
+/// changed without any topology change, recomputes their routes from the
+/// cached `spf` and updates `rib` accordingly. Returns what changed.
+pub fn prc_calc(
+    graph: &Graph,
+    spf: &BTreeMap<usize, Path>,
+    rib: &mut BTreeMap<Prefix, Route>,
+    prefixes: &BTreeSet<Prefix>,
+) -> RouteDiff {
+    let mut routes = routes(graph, spf, |prefix| prefixes.contains(prefix));
+    let mut diff = RouteDiff::default();
+
+    for prefix in prefixes {
+        match (rib.remove(prefix), routes.remove(prefix)) {
+            (None, Some(route)) => {
+                diff.added.insert(*prefix, route.clone());
+                rib.insert(*prefix, route);
+            }
+            (Some(old), Some(route)) => {
+                if old != route {
+                    diff.changed.insert(*prefix, route.clone());
+                }
+                rib.insert(*prefix, route);
+            }
+            (Some(_), None) => {
+                diff.removed.insert(*prefix);
+            }
+            (None, None) => {}
+        }
+    }
+    diff
+}
+
+fn node_names(graph: &Graph, ids: &BTreeSet<usize>) -> String {
+    ids.iter()
+        .map(|id| node_name(graph, *id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn route_table_print(graph: &Graph, rib: &BTreeMap<Prefix, Route>) {
+    for (prefix, route) in rib {
+        print!("{} [{}]", prefix, route.cost);
+        if route.nexthops.is_empty() {
+            print!(" local");
+        } else {
+            print!(" via {}", node_names(graph, &route.nexthops));
+        }
+        println!(" from {}", node_names(graph, &route.nodes));
+    }
+}
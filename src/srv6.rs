@@ -0,0 +1,119 @@
+use std::net::Ipv6Addr;
+
+use crate::{Graph, SpfError, SrSegment, pc_path_ends};
+
+/// SRv6 locator of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Srv6Locator {
+    pub prefix: Ipv6Addr,
+    pub len: u8,
+}
+
+impl Srv6Locator {
+    pub fn new(prefix: Ipv6Addr, len: u8) -> Self {
+        Self { prefix, len }
+    }
+
+    fn mask(&self) -> u128 {
+        match self.len {
+            0 => 0,
+            len if len >= 128 => u128::MAX,
+            len => u128::MAX << (128 - len),
+        }
+    }
+
+    pub fn contains(&self, sid: &Ipv6Addr) -> bool {
+        let mask = self.mask();
+        u128::from(*sid) & mask == u128::from(self.prefix) & mask
+    }
+
+    /// SID of this locator whose bits after the locator are `function`.
+    pub fn sid(&self, function: u128) -> Ipv6Addr {
+        let mask = self.mask();
+        Ipv6Addr::from((u128::from(self.prefix) & mask) | (function & !mask))
+    }
+}
+
+// Locator of an SRv6 capable node, every SID of the node must fall in it.
+fn locator(graph: &Graph, id: usize) -> Result<Option<Srv6Locator>, SpfError> {
+    let node = graph.get(&id).ok_or(SpfError::UnknownNode(id))?;
+    if !node.is_srv6_capable() {
+        return Ok(None);
+    }
+    Ok(node.srv6_locator)
+}
+
+fn end_sid(graph: &Graph, id: usize) -> Result<Ipv6Addr, SpfError> {
+    let locator = locator(graph, id)?.ok_or(SpfError::NoEndSid(id))?;
+    let sid = graph[&id].end_sid.ok_or(SpfError::NoEndSid(id))?;
+    if !locator.contains(&sid) {
+        return Err(SpfError::SidOutsideLocator(id));
+    }
+    Ok(sid)
+}
+
+fn end_x_sid(graph: &Graph, from: usize, to: usize) -> Result<Ipv6Addr, SpfError> {
+    let locator = locator(graph, from)?.ok_or(SpfError::NoEndXSid(from, to))?;
+    let sid = graph[&from]
+        .olinks
+        .iter()
+        .filter(|link| link.to == to && link.is_active())
+        .find_map(|link| link.end_x_sid)
+        .ok_or(SpfError::NoEndXSid(from, to))?;
+    if !locator.contains(&sid) {
+        return Err(SpfError::SidOutsideLocator(from));
+    }
+    Ok(sid)
+}
+
+/// Turns the repair list of a post-convergence path into the SRv6 SIDs S
+/// steers the packet through, first SID first. An SRH carries them in
+/// reverse order. S sends the packet to the first hop of `pc_path`, so an
+/// End.X SID of S itself is not part of the list, and a Node-SID followed
+/// by an Adj-SID of the same node only gives the End.X SID. After the last
+/// SID the packet is forwarded to its original destination.
+pub fn srv6_sid_list(
+    graph: &Graph,
+    pc_path: &[usize],
+    repair_list: &[SrSegment],
+) -> Result<Vec<Ipv6Addr>, SpfError> {
+    let (s, nexthop, _) = pc_path_ends(pc_path)?;
+
+    let mut segments = repair_list;
+    let mut prev = s;
+
+    if let Some(SrSegment::AdjSid(from, to)) = segments.first()
+        && *from == s
+    {
+        if *to != nexthop {
+            return Err(SpfError::InvalidPath(*to));
+        }
+        segments = &segments[1..];
+        prev = *to;
+    }
+
+    let mut sids = Vec::new();
+
+    for (index, segment) in segments.iter().enumerate() {
+        match segment {
+            SrSegment::NodeSid(id) => {
+                // An End.X SID of the node is routed to it through its
+                // locator already.
+                if !matches!(segments.get(index + 1), Some(SrSegment::AdjSid(from, _)) if from == id)
+                {
+                    sids.push(end_sid(graph, *id)?);
+                }
+                prev = *id;
+            }
+            SrSegment::AdjSid(from, to) => {
+                if *from != prev {
+                    return Err(SpfError::InvalidPath(*from));
+                }
+                sids.push(end_x_sid(graph, *from, *to)?);
+                prev = *to;
+            }
+        }
+    }
+
+    Ok(sids)
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use crate::{Graph, Path, SpfDirect};
+
+#[derive(Default)]
+pub struct UcmpOpt {
+    /// Also weight each nexthop by the number of shortest paths it carries
+    /// toward the destination.
+    pub path_count: bool,
+}
+
+impl UcmpOpt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Weighted nexthops of `root` toward each destination of `spf`, the result
+/// of a forward SPF from `root`. A nexthop weighs the bandwidth of the
+/// links from the root to it that are on a shortest path, a link without
+/// bandwidth counting as 1. With `path_count` the weight is multiplied by
+/// the number of shortest paths from the nexthop to the destination.
+/// Bandwidth sums and path counts saturate at `u64::MAX`. Weights are divided by their greatest common divisor. Unlike `spf_calc`
+/// the result is not limited by `path_max`.
+pub fn ucmp_calc(
+    graph: &Graph,
+    root: usize,
+    spf: &BTreeMap<usize, Path>,
+    opt: &UcmpOpt,
+) -> BTreeMap<usize, BTreeMap<usize, u64>> {
+    let direct = SpfDirect::Normal;
+
+    // Bandwidth from the root to each of its nexthops.
+    let mut bandwidth = BTreeMap::<usize, u64>::new();
+    if let (Some(node), Some(path)) = (graph.get(&root), spf.get(&root))
+        && !node.is_disabled
+    {
+        for link in node.links(&direct) {
+            let to = link.id(&direct);
+            if !link.is_active() || to == root {
+                continue;
+            }
+            if spf
+                .get(&to)
+                .is_some_and(|nhop| path.cost.saturating_add(link.cost) == nhop.cost)
+            {
+                let weight = bandwidth.entry(to).or_default();
+                *weight = weight.saturating_add(link.bandwidth.unwrap_or(1));
+            }
+        }
+    }
+
+    // Number of shortest paths to each node per nexthop, walking the nodes
+    // in SPF order. Without `path_count` a nexthop only has to be there.
+    let mut order = spf.values().collect::<Vec<_>>();
+    order.sort_by_key(|path| (path.cost, path.id));
+
+    let mut counts = BTreeMap::<usize, BTreeMap<usize, u64>>::new();
+    for nhop in bandwidth.keys() {
+        counts.entry(*nhop).or_default().insert(*nhop, 1);
+    }
+    for path in order {
+        if path.id == root {
+            continue;
+        }
+        let Some(node) = graph.get(&path.id) else {
+            continue;
+        };
+        if node.is_disabled {
+            continue;
+        }
+        let Some(count) = counts.get(&path.id).cloned() else {
+            continue;
+        };
+        for link in node.links(&direct) {
+            let to = link.id(&direct);
+            if !link.is_active() || to == root {
+                continue;
+            }
+            if spf
+                .get(&to)
+                .is_some_and(|next| path.cost.saturating_add(link.cost) == next.cost)
+            {
+                let next = counts.entry(to).or_default();
+                for (nhop, n) in &count {
+                    let next = next.entry(*nhop).or_default();
+                    *next = if opt.path_count {
+                        next.saturating_add(*n)
+                    } else {
+                        1
+                    };
+                }
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(id, count)| {
+            let mut weights = count
+                .into_iter()
+                .map(|(nhop, n)| {
+                    let weight = bandwidth[&nhop];
+                    let weight = if opt.path_count {
+                        weight.saturating_mul(n)
+                    } else {
+                        weight
+                    };
+                    (nhop, weight)
+                })
+                .collect::<BTreeMap<_, _>>();
+            let divisor = weights.values().fold(0, |acc, w| gcd(acc, *w));
+            if divisor > 1 {
+                for weight in weights.values_mut() {
+                    *weight /= divisor;
+                }
+            }
+            (id, weights)
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+use std::net::Ipv6Addr;
+
+/// Micro-SID format: the length in bits of the locator block shared by the
+/// uSIDs of a container, and of each uSID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsidFormat {
+    pub block_len: u8,
+    pub usid_len: u8,
+}
+
+impl UsidFormat {
+    /// 32-bit block and 16-bit uSIDs, six uSIDs per container.
+    pub const F3216: UsidFormat = UsidFormat {
+        block_len: 32,
+        usid_len: 16,
+    };
+
+    pub fn new(block_len: u8, usid_len: u8) -> Self {
+        Self {
+            block_len,
+            usid_len,
+        }
+    }
+
+    /// Number of uSIDs a container holds after the block.
+    pub fn capacity(&self) -> usize {
+        if self.usid_len == 0 || self.block_len >= 128 {
+            return 0;
+        }
+        (128 - self.block_len as usize) / self.usid_len as usize
+    }
+
+    fn usid_shift(&self, index: usize) -> u32 {
+        (128 - self.block_len as usize - (index + 1) * self.usid_len as usize) as u32
+    }
+
+    fn usid_mask(&self) -> u128 {
+        1u128
+            .checked_shl(self.usid_len as u32)
+            .map_or(u128::MAX, |bit| bit - 1)
+    }
+
+    // Block and uSIDs of `sid`, or None when the SID is not a uSID
+    // container: no uSID, or non-zero bits after the first zero uSID.
+    fn split(&self, sid: &Ipv6Addr) -> Option<(u128, Vec<u128>)> {
+        let capacity = self.capacity();
+        if capacity == 0 {
+            return None;
+        }
+        let bits = u128::from(*sid);
+        let block = bits.checked_shr(128 - self.block_len as u32).unwrap_or(0);
+
+        let mut usids = Vec::new();
+        for index in 0..capacity {
+            let usid = (bits >> self.usid_shift(index)) & self.usid_mask();
+            if usid == 0 {
+                break;
+            }
+            usids.push(usid);
+        }
+        if usids.is_empty() {
+            return None;
+        }
+
+        let used = self.block_len as u32 + usids.len() as u32 * self.usid_len as u32;
+        let rest = bits.checked_shl(used).unwrap_or(0);
+        if rest != 0 {
+            return None;
+        }
+        Some((block, usids))
+    }
+
+    fn container(&self, block: u128, usids: &[u128]) -> Ipv6Addr {
+        let mut bits = block.checked_shl(128 - self.block_len as u32).unwrap_or(0);
+        for (index, usid) in usids.iter().enumerate() {
+            bits |= usid << self.usid_shift(index);
+        }
+        Ipv6Addr::from(bits)
+    }
+}
+
+/// Packs the SRv6 SID list `sids`, first SID first, into uSID containers.
+/// Consecutive SIDs in uSID form that share the locator block are merged
+/// into one container until it is full. Any other SID is kept as is and
+/// ends the current container.
+pub fn usid_compress(sids: &[Ipv6Addr], format: &UsidFormat) -> Vec<Ipv6Addr> {
+    let capacity = format.capacity();
+    let mut containers = Vec::new();
+    let mut current: Option<(u128, Vec<u128>)> = None;
+
+    for sid in sids {
+        let Some((block, usids)) = format.split(sid) else {
+            if let Some((block, usids)) = current.take() {
+                containers.push(format.container(block, &usids));
+            }
+            containers.push(*sid);
+            continue;
+        };
+
+        for usid in usids {
+            if let Some((cur_block, cur_usids)) = &mut current
+                && *cur_block == block
+                && cur_usids.len() < capacity
+            {
+                cur_usids.push(usid);
+                continue;
+            }
+            if let Some((block, usids)) = current.take() {
+                containers.push(format.container(block, &usids));
+            }
+            current = Some((block, vec![usid]));
+        }
+    }
+
+    if let Some((block, usids)) = current {
+        containers.push(format.container(block, &usids));
+    }
+    containers
+}
@@ -0,0 +1,53 @@
+use spf::*;
+
+// Ring of n nodes with unit costs and a chord 0 - n/2 of cost n/2.
+pub fn ring_chord_graph(n: usize) -> Graph {
+    let mut graph = GraphBuilder::new();
+    for id in 0..n {
+        graph.add_node(Node::new(&id.to_string(), id * 2));
+    }
+    for id in 0..n {
+        graph.add_bidir_link(Link::new(id * 2, ((id + 1) % n) * 2, 1));
+    }
+    graph.add_bidir_link(Link::new(0, n, n as u32 / 2));
+    graph.build()
+}
+
+#[test]
+pub fn all_pairs() {
+    let mut graph = ring_chord_graph(12);
+    graph.get_mut(&22).unwrap().is_disabled = true;
+
+    let matrix = all_pairs_spf(&graph, 1);
+    assert_eq!(matrix.len(), 12);
+
+    // 0 reaches 12 over both halves of the ring and the chord.
+    assert_eq!(matrix.cost(0, 12), Some(6));
+    assert_eq!(
+        matrix
+            .nexthops(0, 12)
+            .unwrap()
+            .iter()
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![2, 12]
+    );
+    assert_eq!(matrix.cost(0, 0), Some(0));
+    assert!(matrix.nexthops(0, 0).unwrap().is_empty());
+    assert_eq!(matrix.cost(0, 22), None);
+    assert_eq!(matrix.cost(0, 1), None);
+    assert_eq!(matrix.nexthops(0, 1), None);
+
+    // Same as SPF from each node.
+    let opt = SpfOpt::default();
+    for from in graph.keys() {
+        let spf = spf(&graph, *from, &opt);
+        for to in graph.keys() {
+            assert_eq!(matrix.cost(*from, *to), spf.get(to).map(|path| path.cost));
+        }
+    }
+
+    for threads in [0, 2, 3, 8, 64] {
+        assert_eq!(all_pairs_spf(&graph, threads), matrix);
+    }
+}
@@ -0,0 +1,39 @@
+use spf::*;
+
+#[test]
+pub fn builder() {
+    let mut graph = GraphBuilder::new();
+
+    assert!(graph.add_node(Node::new("A", 0)));
+    assert!(graph.add_node(Node::new("B", 1)));
+    assert!(graph.add_node(Node::new("C", 2)));
+    assert!(!graph.add_node(Node::new("A'", 0)));
+
+    assert!(graph.add_bidir_link(Link::new(0, 1, 10)));
+    assert!(graph.add_link(Link::new(1, 2, 10)));
+
+    // Endpoints must exist.
+    assert!(!graph.add_link(Link::new(2, 3, 10)));
+    assert!(!graph.add_bidir_link(Link::new(3, 0, 10)));
+
+    let a = &graph.graph()[&0];
+    assert_eq!(a.olinks, vec![Link::new(0, 1, 10)]);
+    assert_eq!(a.ilinks, vec![Link::new(1, 0, 10)]);
+    let c = &graph.graph()[&2];
+    assert!(c.olinks.is_empty());
+    assert_eq!(c.ilinks, vec![Link::new(1, 2, 10)]);
+
+    // Reverse SPF follows the ilinks.
+    let spf = spf_reverse(graph.graph(), 2, &SpfOpt::default());
+    assert_eq!(spf.get(&0).map(|path| path.cost), Some(20));
+
+    assert!(graph.remove_link(&Link::new(1, 2, 10)));
+    assert!(!graph.remove_link(&Link::new(1, 2, 10)));
+
+    let graph = graph.build();
+    assert!(graph[&1].olinks.iter().all(|link| link.to != 2));
+    assert!(graph[&2].ilinks.is_empty());
+
+    let spf = spf_reverse(&graph, 2, &SpfOpt::default());
+    assert_eq!(spf.len(), 1);
+}
@@ -0,0 +1,126 @@
+// Fixtures shared by the integration tests, each test crate uses a part of
+// them.
+#![allow(dead_code)]
+
+use spf::*;
+
+// Ring of n nodes with cost 1 links.
+pub fn ring_topology(n: usize) -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    for id in 0..n {
+        graph.add_node(Node::new(&id.to_string(), id));
+    }
+
+    for from in 0..n {
+        let to = (from + 1) % n;
+        graph.add_bidir_link(Link::new(from, to, 1));
+    }
+
+    graph.build()
+}
+
+// Xorshift, good enough to shuffle topologies reproducibly.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Random graph of n nodes and up to `links` links of cost 1 to `cost_max`,
+// bidirectional unless `one_way` makes one in four of them unidirectional.
+pub fn random_graph(
+    rng: &mut Rng,
+    n: usize,
+    links: usize,
+    cost_max: usize,
+    one_way: bool,
+) -> Graph {
+    let mut graph = GraphBuilder::new();
+    for id in 0..n {
+        graph.add_node(Node::new(&id.to_string(), id));
+    }
+    for _ in 0..links {
+        let from = rng.below(n);
+        let to = rng.below(n);
+        if from == to {
+            continue;
+        }
+        let link = Link::new(from, to, 1 + rng.below(cost_max) as u32);
+        if one_way && rng.below(4) == 0 {
+            graph.add_link(link);
+        } else {
+            graph.add_bidir_link(link);
+        }
+    }
+    graph.build()
+}
+
+//  +---+ +---+ +---+ +---+
+//  | 0 |-| 1 |-|...|-|n-1|
+//  +---+ +---+ +---+ +---+
+//    |     |     |     |
+//  +---+ +---+ +---+ +---+
+//  | n |-|n+1|-|...|-|2n|
+//  +---+ +---+ +---+ +---+
+//    |     |     |     |
+//  +---+ +---+ +---+ +---+
+//  |Xn |-| 1 |-|...|-|   |
+//  +---+ +---+ +---+ +---+
+//
+// Square matrix of n x n nodes with links toward the right and the bottom
+// neighbors, node ids scaled by `step`.
+pub fn matrix_graph(n: usize, step: usize) -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    for id in 0..n * n {
+        graph.add_node(Node::new(&id.to_string(), id * step));
+    }
+
+    for i in 0..n {
+        for j in 0..n {
+            let id = (i * n) + j;
+
+            // Vertical link: do not create for bottom row
+            if i != n - 1 {
+                graph.add_link(Link::new(id * step, (id + n) * step, 10));
+            }
+
+            // Horizontal link: do not create for rightmost column
+            if j != n - 1 {
+                graph.add_link(Link::new(id * step, (id + 1) * step, 10));
+            }
+        }
+    }
+
+    graph.build()
+}
+
+// Grid of n x n nodes with cost 1 links in both directions.
+pub fn grid_graph(n: usize) -> Graph {
+    let mut graph = GraphBuilder::new();
+    for id in 0..n * n {
+        graph.add_node(Node::new(&id.to_string(), id));
+    }
+    for i in 0..n {
+        for j in 0..n {
+            let id = i * n + j;
+            if i != n - 1 {
+                graph.add_bidir_link(Link::new(id, id + n, 1));
+            }
+            if j != n - 1 {
+                graph.add_bidir_link(Link::new(id, id + 1, 1));
+            }
+        }
+    }
+    graph.build()
+}
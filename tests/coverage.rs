@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+
+use spf::*;
+
+mod common;
+use common::*;
+
+fn coverage(lfa: usize, node_sid: usize, node_adj: usize, unprotected: usize) -> Coverage {
+    Coverage {
+        lfa,
+        node_sid,
+        node_adj,
+        longer: 0,
+        unprotected,
+    }
+}
+
+#[test]
+pub fn coverage_kind() {
+    use SrSegment::*;
+
+    assert_eq!(CoverageKind::new(0, &[]), CoverageKind::Lfa);
+    assert_eq!(CoverageKind::new(0, &[AdjSid(0, 1)]), CoverageKind::Lfa);
+    assert_eq!(CoverageKind::new(0, &[NodeSid(3)]), CoverageKind::NodeSid);
+    assert_eq!(
+        CoverageKind::new(0, &[AdjSid(0, 1), NodeSid(3), AdjSid(3, 2)]),
+        CoverageKind::NodeAdj
+    );
+    assert_eq!(CoverageKind::new(0, &[AdjSid(1, 2)]), CoverageKind::Longer);
+    assert_eq!(
+        CoverageKind::new(0, &[NodeSid(3), AdjSid(3, 2), AdjSid(2, 1)]),
+        CoverageKind::Longer
+    );
+}
+
+#[test]
+pub fn coverage_ring() {
+    let opt = TilfaOpt::default();
+
+    // Toward X itself the repair releases at the far node of the P space.
+    let report = tilfa_coverage(&ring_topology(5), &opt);
+    assert_eq!(report.len(), 5);
+    for c in report.values() {
+        assert_eq!(*c, coverage(6, 2, 0, 0));
+    }
+
+    // With an even ring the node opposite to S is reached over the failure
+    // as well, so an Adj-SID is needed past it.
+    let report = tilfa_coverage(&ring_topology(4), &opt);
+    for c in report.values() {
+        assert_eq!(*c, coverage(4, 0, 2, 0));
+    }
+    let total = coverage_total(&report);
+    assert_eq!(total, coverage(16, 0, 8, 0));
+    assert_eq!(total.ratio(), 100.0);
+
+    let csv = coverage_csv(&ring_topology(4), &report);
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("node,lfa,node_sid,node_adj,longer,unprotected,total")
+    );
+    assert_eq!(lines.next(), Some("0,4,0,2,0,0,6"));
+    assert_eq!(lines.count(), 3);
+
+    coverage_print(&ring_topology(4), &report);
+}
+
+#[test]
+pub fn coverage_unprotected() {
+    // Tail node 3 hangs off the ring of 3 through node 0.
+    let mut graph = GraphBuilder::new();
+    for id in 0..4 {
+        graph.add_node(Node::new(&id.to_string(), id));
+    }
+    for (from, to) in [(0, 1), (1, 2), (2, 0), (0, 3)] {
+        graph.add_bidir_link(Link::new(from, to, 1));
+    }
+    let mut graph = graph.build();
+    graph.get_mut(&2).unwrap().is_disabled = true;
+
+    let report = tilfa_coverage(&graph, &TilfaOpt::default());
+    assert!(!report.contains_key(&2));
+
+    // 3 loses everything with its only link, 0 and 1 lose each other.
+    assert_eq!(report[&3], coverage(0, 0, 0, 2));
+    assert_eq!(report[&0], coverage(2, 0, 0, 2));
+    assert_eq!(report[&1], coverage(0, 0, 0, 2));
+    assert_eq!(coverage_total(&report).ratio(), 25.0);
+}
+
+#[test]
+pub fn coverage_tilfa() {
+    // The shared P and Q spaces give the same report as one tilfa() per
+    // (S, X, D) tuple.
+    let mut rng = Rng(11);
+    let graph = random_graph(&mut rng, 15, 30, 4, true);
+    let mut opts = [TilfaOpt::new(), TilfaOpt::new(), TilfaOpt::new()];
+    opts[1].extended_p_space = true;
+    opts[2].protection = Protection::Node;
+
+    for opt in &opts {
+        let mut expect = CoverageReport::new();
+        for s in graph.keys() {
+            let coverage = expect.entry(*s).or_default();
+            let reach = spf(&graph, *s, &SpfOpt::default());
+            for x in graph[s]
+                .olinks
+                .iter()
+                .map(|link| link.to)
+                .collect::<BTreeSet<_>>()
+            {
+                for d in reach.keys().filter(|d| *d != s) {
+                    let kind = match tilfa(&graph, *s, *d, x, opt) {
+                        Ok(result) => result
+                            .paths
+                            .iter()
+                            .map(|path| CoverageKind::new(*s, &path.repair_list))
+                            .min()
+                            .unwrap(),
+                        Err(_) => CoverageKind::Unprotected,
+                    };
+                    coverage.add(kind);
+                }
+            }
+        }
+        assert_eq!(tilfa_coverage(&graph, opt), expect);
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::Instant;
+
+use spf::*;
+
+mod common;
+use common::*;
+
+#[test]
+pub fn csr_index() {
+    let graph = matrix_graph(3, 7);
+    let csr = CsrGraph::from(&graph);
+
+    assert_eq!(csr.len(), 9);
+    assert_eq!(csr.index(28), Some(4));
+    assert_eq!(csr.index(29), None);
+    assert_eq!(csr.id(4), Some(28));
+    assert_eq!(csr.id(9), None);
+
+    let edges = |links: &[Link], direct| {
+        links
+            .iter()
+            .map(|link: &Link| (csr.index(link.id(direct)).unwrap(), link.cost))
+            .collect::<Vec<_>>()
+    };
+    let normal = csr.index_edges(4, &SpfDirect::Normal).collect::<Vec<_>>();
+    assert_eq!(normal, edges(&graph[&28].olinks, &SpfDirect::Normal));
+    let reverse = csr.index_edges(4, &SpfDirect::Reverse).collect::<Vec<_>>();
+    assert_eq!(reverse, edges(&graph[&28].ilinks, &SpfDirect::Reverse));
+    assert_eq!(csr.index_edges(9, &SpfDirect::Normal).count(), 0);
+}
+
+#[test]
+pub fn csr_spf() {
+    let nexthop = SpfOpt::default();
+    let full_path = SpfOpt {
+        full_path: true,
+        path_max: 8,
+        ..Default::default()
+    };
+
+    for step in [1, 3] {
+        let mut graph = matrix_graph(20, step);
+        graph.get_mut(&(21 * step)).unwrap().is_disabled = true;
+        let csr = CsrGraph::new(&graph);
+
+        for opt in [&nexthop, &full_path] {
+            assert_eq!(spf(&csr, 0, opt), spf(&graph, 0, opt));
+            let root = 399 * step;
+            assert_eq!(spf_reverse(&csr, root, opt), spf_reverse(&graph, root, opt));
+        }
+        for direct in [SpfDirect::Normal, SpfDirect::Reverse] {
+            assert_eq!(spf_dag(&csr, 0, &direct), spf_dag(&graph, 0, &direct));
+        }
+    }
+}
+
+#[test]
+pub fn csr_failure() {
+    let mut graph = matrix_graph(10, 1);
+    for link in graph.get_mut(&0).unwrap().olinks.iter_mut() {
+        if link.to == 1 {
+            link.is_up = false;
+        }
+    }
+    let csr = CsrGraph::new(&graph);
+    let opt = SpfOpt::default();
+
+    let mut failure = Failure::link(&graph, 10, 20);
+    failure.add_node(55);
+    for direct in [SpfDirect::Normal, SpfDirect::Reverse] {
+        let graph_spf = spf_calc_with(&graph, 0, &opt, &direct, &failure);
+        assert_eq!(spf_calc_with(&csr, 0, &opt, &direct, &failure), graph_spf);
+    }
+    assert!(!spf(&csr, 0, &opt).contains_key(&1));
+
+    // Parallel links only differing by their risk groups.
+    let mut graph = matrix_graph(10, 1);
+    for srlgs in [vec![1], vec![2]] {
+        let mut link = Link::new(0, 10, 1);
+        link.srlgs = srlgs;
+        graph.get_mut(&0).unwrap().olinks.push(link.clone());
+        graph.get_mut(&10).unwrap().ilinks.push(link);
+    }
+    let csr = CsrGraph::new(&graph);
+    let mut failure = Failure::new();
+    failure.add_srlg(&graph, 1);
+    failure.add_link(Link::new(0, 10, 1));
+    for root in [0, 10] {
+        let graph_spf = spf_calc_with(&graph, root, &opt, &SpfDirect::Reverse, &failure);
+        assert_eq!(
+            spf_calc_with(&csr, root, &opt, &SpfDirect::Reverse, &failure),
+            graph_spf
+        );
+    }
+    let graph_spf = spf_calc_with(&graph, 0, &opt, &SpfDirect::Normal, &failure);
+    assert_eq!(graph_spf[&10].cost, 1);
+    assert_eq!(
+        spf_calc_with(&csr, 0, &opt, &SpfDirect::Normal, &failure),
+        graph_spf
+    );
+    failure.add_srlg(&graph, 2);
+    let graph_spf = spf_calc_with(&graph, 0, &opt, &SpfDirect::Normal, &failure);
+    assert_eq!(graph_spf[&10].cost, 10);
+    assert_eq!(
+        spf_calc_with(&csr, 0, &opt, &SpfDirect::Normal, &failure),
+        graph_spf
+    );
+}
+
+#[test]
+pub fn csr_matrix() {
+    let graph = matrix_graph(100, 1);
+    let csr = CsrGraph::new(&graph);
+    let opt = SpfOpt::default();
+
+    let now = Instant::now();
+    let graph_spf = spf(&graph, 0, &opt);
+    println!("graph n:100 {:?}", now.elapsed());
+
+    let now = Instant::now();
+    let csr_spf = spf(&csr, 0, &opt);
+    println!("csr n:100 {:?}", now.elapsed());
+
+    assert_eq!(csr_spf, graph_spf);
+}
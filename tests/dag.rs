@@ -0,0 +1,62 @@
+use std::collections::BTreeSet;
+
+use spf::*;
+
+mod common;
+use common::*;
+
+#[test]
+pub fn dag_compare() {
+    let full_path = SpfOpt {
+        full_path: true,
+        ..Default::default()
+    };
+    let nexthop = SpfOpt::default();
+
+    for seed in 1..=20 {
+        let mut rng = Rng(seed);
+        let mut graph = random_graph(&mut rng, 25, 60, 3, false);
+        graph.get_mut(&3).unwrap().is_disabled = true;
+
+        for direct in [SpfDirect::Normal, SpfDirect::Reverse] {
+            let dag = spf_dag(&graph, 0, &direct);
+            let spf = spf_calc(&graph, 0, &full_path, &direct);
+            let nhops = spf_calc(&graph, 0, &nexthop, &direct);
+
+            assert_eq!(
+                dag.nodes.keys().collect::<Vec<_>>(),
+                spf.keys().collect::<Vec<_>>()
+            );
+            for (id, path) in &spf {
+                assert_eq!(dag.cost(*id), Some(path.cost));
+                assert_eq!(dag.paths(*id).collect::<Vec<_>>(), path.paths);
+                assert_eq!(dag.path_count(*id), path.paths.len() as u64);
+
+                let nexthops = nhops[id]
+                    .nexthops
+                    .iter()
+                    .filter_map(|nhop| nhop.get(1).copied())
+                    .collect::<BTreeSet<_>>();
+                assert_eq!(dag.nexthops(*id), nexthops);
+            }
+        }
+    }
+}
+
+#[test]
+pub fn dag_unreachable() {
+    let mut graph = GraphBuilder::new();
+    graph.add_node(Node::new("N0", 0));
+    graph.add_node(Node::new("N1", 1));
+    let graph = graph.build();
+
+    let dag = spf_dag(&graph, 0, &SpfDirect::Normal);
+    assert_eq!(dag.paths(0).collect::<Vec<_>>(), vec![vec![0]]);
+    assert_eq!(dag.path_count(0), 1);
+    assert!(dag.nexthops(0).is_empty());
+
+    assert_eq!(dag.cost(1), None);
+    assert_eq!(dag.paths(1).next(), None);
+    assert_eq!(dag.path_count(1), 0);
+    assert!(dag.nexthops(1).is_empty());
+}
@@ -1,10 +1,9 @@
-use std::collections::BTreeMap;
 use std::time;
 
 use spf::*;
 
 pub fn ecmp_topology() -> Graph {
-    let mut graph = BTreeMap::new();
+    let mut graph = GraphBuilder::new();
 
     // First, insert all nodes
     let nodes = vec![
@@ -15,9 +14,9 @@ pub fn ecmp_topology() -> Graph {
         Node::new("N5", 4),
     ];
 
-    // Insert each Node into the map keyed by node id
+    // Insert each Node into the graph keyed by node id
     for node in nodes {
-        graph.insert(node.id, node);
+        graph.add_node(node);
     }
 
     // Define links between nodes
@@ -36,16 +35,12 @@ pub fn ecmp_topology() -> Graph {
         (4, 3, 10),
     ];
 
-    // Now add links to the respective nodes stored in our BTreeMap
+    // Now add links to the respective nodes stored in our graph
     for (from, to, cost) in links {
-        graph
-            .get_mut(&from)
-            .unwrap()
-            .olinks
-            .push(Link::new(from, to, cost));
+        graph.add_link(Link::new(from, to, cost));
     }
 
-    graph
+    graph.build()
 }
 #[test]
 pub fn ecmp() {
@@ -54,6 +49,7 @@ pub fn ecmp() {
         path_max: 32,
         srv6: false,
         srmpls: true,
+        ..Default::default()
     };
 
     let graph = ecmp_topology();
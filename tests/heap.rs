@@ -0,0 +1,41 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use spf::*;
+
+mod common;
+use common::*;
+
+#[test]
+pub fn radix_heap() {
+    let mut radix = RadixHeap::new();
+    let mut binary = BinaryHeap::new();
+    assert!(radix.is_empty());
+    assert_eq!(radix.pop(), None);
+
+    // Dijkstra like workload: each pop pushes a few keys above it.
+    let mut rng = Rng(7);
+    let mut pushes = 0;
+    radix.push(0, 0);
+    binary.push(Reverse((0u32, 0usize)));
+
+    while let Some(Reverse(expected)) = binary.pop() {
+        assert_eq!(radix.len(), binary.len() + 1);
+        let key = radix.pop();
+        assert_eq!(key, Some(expected));
+
+        if pushes > 10000 {
+            continue;
+        }
+        pushes += 3;
+        for _ in 0..3 {
+            let seed = rng.next();
+            let cost = expected.0 + (seed % 4) as u32;
+            let id = (seed % 5000) as usize;
+            radix.push(cost, id);
+            binary.push(Reverse((cost, id)));
+        }
+    }
+    assert!(radix.is_empty());
+    assert_eq!(radix.pop(), None);
+}
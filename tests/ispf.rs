@@ -0,0 +1,199 @@
+use spf::*;
+
+mod common;
+use common::*;
+
+fn random_node(rng: &mut Rng, graph: &Graph) -> usize {
+    *graph.keys().nth(rng.below(graph.len())).unwrap()
+}
+
+fn random_link(rng: &mut Rng, graph: &Graph) -> Option<Link> {
+    let node = &graph[&random_node(rng, graph)];
+    if node.olinks.is_empty() {
+        return None;
+    }
+    Some(node.olinks[rng.below(node.olinks.len())].clone())
+}
+
+fn remove_link(graph: &mut Graph, link: &Link) {
+    if let Some(node) = graph.get_mut(&link.from)
+        && let Some(pos) = node.olinks.iter().position(|l| l == link)
+    {
+        node.olinks.remove(pos);
+    }
+    if let Some(node) = graph.get_mut(&link.to)
+        && let Some(pos) = node.ilinks.iter().position(|l| l == link)
+    {
+        node.ilinks.remove(pos);
+    }
+}
+
+// Removes node `id` together with every link attached to it.
+fn remove_node(graph: &mut Graph, id: usize) {
+    graph.remove(&id);
+    for node in graph.values_mut() {
+        node.olinks.retain(|link| link.to != id);
+        node.ilinks.retain(|link| link.from != id);
+    }
+}
+
+fn insert_link(graph: &mut Graph, link: Link) {
+    graph.get_mut(&link.to).unwrap().ilinks.push(link.clone());
+    graph.get_mut(&link.from).unwrap().olinks.push(link);
+}
+
+// Applies one random change to `graph` and records it.
+fn random_change(rng: &mut Rng, graph: &mut Graph, change: &mut SpfChange) {
+    match rng.below(7) {
+        0 => {
+            if let Some(link) = random_link(rng, graph) {
+                link_set_up(graph, &link, !link.is_up);
+                change.add_link(link);
+            }
+        }
+        1 => {
+            if let Some(link) = random_link(rng, graph) {
+                link_set_disabled(graph, &link, !link.is_disabled);
+                change.add_link(link);
+            }
+        }
+        2 => {
+            if let Some(link) = random_link(rng, graph) {
+                let mut new = link.clone();
+                new.cost = 1 + rng.below(10) as u32;
+                remove_link(graph, &link);
+                insert_link(graph, new);
+                change.add_link(link);
+            }
+        }
+        3 => {
+            let id = random_node(rng, graph);
+            let node = graph.get_mut(&id).unwrap();
+            node.is_disabled = !node.is_disabled;
+            change.add_node(id);
+        }
+        4 => {
+            let from = random_node(rng, graph);
+            let to = random_node(rng, graph);
+            if from != to {
+                let link = Link::new(from, to, 1 + rng.below(10) as u32);
+                insert_link(graph, link.clone());
+                change.add_link(link);
+            }
+        }
+        5 => {
+            if let Some(link) = random_link(rng, graph) {
+                remove_link(graph, &link);
+                change.add_link(link);
+            }
+        }
+        _ => {
+            // Keep the root.
+            let id = random_node(rng, graph);
+            if id != 0 {
+                remove_node(graph, id);
+                change.add_node(id);
+            }
+        }
+    }
+}
+
+fn ispf_compare(seed: u64, opt: &SpfOpt, direct: &SpfDirect) {
+    let mut rng = Rng(seed);
+    let mut graph = random_graph(&mut rng, 30, 60, 10, true);
+    let root = 0;
+    let mut prev = spf_calc(&graph, root, opt, direct);
+
+    for _ in 0..40 {
+        let mut change = SpfChange::new();
+        for _ in 0..1 + rng.below(3) {
+            random_change(&mut rng, &mut graph, &mut change);
+        }
+
+        // Chain incremental results so that errors would accumulate.
+        let ispf = ispf_calc(&graph, root, opt, direct, prev, &change);
+        let full = spf_calc(&graph, root, opt, direct);
+        assert_eq!(ispf, full, "seed {} change {:?}", seed, change);
+        prev = ispf;
+    }
+}
+
+#[test]
+pub fn ispf_random() {
+    let opts = [
+        SpfOpt {
+            full_path: true,
+            ..Default::default()
+        },
+        SpfOpt {
+            full_path: true,
+            path_max: 2,
+            ..Default::default()
+        },
+        SpfOpt::default(),
+    ];
+    for seed in 1..=20 {
+        for opt in &opts {
+            ispf_compare(seed, opt, &SpfDirect::Normal);
+            ispf_compare(seed, opt, &SpfDirect::Reverse);
+        }
+    }
+}
+
+#[test]
+pub fn ispf_link_flap() {
+    //  0 --1-- 1 --1-- 2
+    //  |               |
+    //  +-------5-------+
+    let mut graph = GraphBuilder::new();
+    for id in 0..3 {
+        graph.add_node(Node::new(&id.to_string(), id));
+    }
+    graph.add_bidir_link(Link::new(0, 1, 1));
+    graph.add_bidir_link(Link::new(1, 2, 1));
+    graph.add_bidir_link(Link::new(0, 2, 5));
+    let mut graph = graph.build();
+
+    let opt = SpfOpt {
+        full_path: true,
+        ..Default::default()
+    };
+    let prev = spf(&graph, 0, &opt);
+    assert_eq!(prev[&2].paths, vec![vec![0, 1, 2]]);
+
+    let link = Link::new(1, 2, 1);
+    link_set_up(&mut graph, &link, false);
+    let mut change = SpfChange::new();
+    change.add_link(link.clone());
+
+    let down = ispf_calc(&graph, 0, &opt, &SpfDirect::Normal, prev.clone(), &change);
+    assert_eq!(down[&2].cost, 5);
+    assert_eq!(down[&2].paths, vec![vec![0, 2]]);
+    assert_eq!(down, spf(&graph, 0, &opt));
+
+    link_set_up(&mut graph, &link, true);
+    let up = ispf_calc(&graph, 0, &opt, &SpfDirect::Normal, down, &change);
+    assert_eq!(up, prev);
+}
+
+#[test]
+pub fn ispf_node_remove() {
+    //  0 --1-- 1 --1-- 2
+    let mut graph = GraphBuilder::new();
+    for id in 0..3 {
+        graph.add_node(Node::new(&id.to_string(), id));
+    }
+    graph.add_bidir_link(Link::new(0, 1, 1));
+    graph.add_bidir_link(Link::new(1, 2, 1));
+    let mut graph = graph.build();
+
+    let opt = SpfOpt::default();
+    let prev = spf(&graph, 0, &opt);
+
+    remove_node(&mut graph, 1);
+    let mut change = SpfChange::new();
+    change.add_node(1);
+
+    let spf = ispf_calc(&graph, 0, &opt, &SpfDirect::Normal, prev, &change);
+    assert_eq!(spf.keys().copied().collect::<Vec<_>>(), vec![0]);
+}
@@ -0,0 +1,95 @@
+use spf::*;
+
+//       +---10---- E ---10---+
+//       |          |         |
+//       |          10        |
+//       |          |         |
+//  S ---+---10--- N2         D
+//       |                    |
+//       +---10--- N1 ---20---+
+//       |                    |
+//       +---10--- N3 ---15---+
+//       |                    |
+//       +---10--- N4 ---10---+
+//
+pub fn lfa_topology() -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    let nodes = vec![
+        Node::new("S", 0),
+        Node::new("E", 1),
+        Node::new("D", 2),
+        Node::new("N1", 3),
+        Node::new("N2", 4),
+        Node::new("N3", 5),
+        Node::new("N4", 6),
+    ];
+
+    for node in nodes {
+        graph.add_node(node);
+    }
+
+    let links = vec![
+        (0, 1, 10),
+        (1, 2, 10),
+        (0, 3, 10),
+        (3, 2, 20),
+        (0, 4, 10),
+        (4, 1, 10),
+        (0, 5, 10),
+        (5, 2, 15),
+        (0, 6, 10),
+        (6, 2, 10),
+    ];
+
+    for (from, to, cost) in links {
+        graph.add_bidir_link(Link::new(from, to, cost));
+    }
+
+    graph.build()
+}
+
+fn find(lfas: &[Lfa], primary: usize, alternate: usize) -> &Lfa {
+    lfas.iter()
+        .find(|lfa| lfa.primary == primary && lfa.alternate == alternate)
+        .unwrap()
+}
+
+#[test]
+pub fn lfa_classify() {
+    let graph = lfa_topology();
+    let lfas = lfa(&graph, 0);
+
+    // D is reached over E and N4 at equal cost.
+    let d = lfas.get(&2).unwrap();
+    for lfa in d {
+        println!("{:?}", lfa);
+    }
+    assert_eq!(d.len(), 8);
+
+    // N1 and N2 are loop-free but not downstream.
+    let n1 = find(d, 1, 3);
+    assert!(n1.link_protecting && n1.node_protecting);
+    assert!(!n1.downstream && !n1.ecmp);
+
+    // N2 reaches D through E, so it only protects the S-E link.
+    let n2 = find(d, 1, 4);
+    assert!(n2.link_protecting && !n2.node_protecting);
+    assert!(!n2.downstream && !n2.ecmp);
+
+    let n3 = find(d, 1, 5);
+    assert!(n3.link_protecting && n3.node_protecting && n3.downstream);
+    assert!(!n3.ecmp);
+
+    // The two primary nexthops protect each other.
+    let n4 = find(d, 1, 6);
+    assert!(n4.node_protecting && n4.downstream && n4.ecmp);
+    let e = find(d, 6, 1);
+    assert!(e.node_protecting && e.downstream && e.ecmp);
+
+    // E is the only primary toward N2. N1 reaches N2 back through S.
+    let n2 = lfas.get(&4).unwrap();
+    assert!(n2.iter().all(|lfa| lfa.primary == 4));
+    assert!(n2.iter().all(|lfa| lfa.alternate != 3));
+    assert!(!find(n2, 4, 1).node_protecting);
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use spf::*;
+
+//        10
+//   +-----------+
+//   |    20     |
+//  A+-----------+B ---10--- C
+//   |           |
+//   +----50-----D----10-----+
+//
+pub fn parallel_topology() -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    let nodes = vec![
+        Node::new("A", 0),
+        Node::new("B", 1),
+        Node::new("C", 2),
+        Node::new("D", 3),
+    ];
+
+    for node in nodes {
+        graph.add_node(node);
+    }
+
+    let links = vec![(0, 1, 10), (0, 1, 20), (1, 2, 10), (0, 3, 50), (3, 2, 10)];
+
+    for (from, to, cost) in links {
+        graph.add_bidir_link(Link::new(from, to, cost));
+    }
+
+    graph.build()
+}
+
+fn cost(spf: &BTreeMap<usize, Path>, id: usize) -> Option<u32> {
+    spf.get(&id).map(|path| path.cost)
+}
+
+#[test]
+pub fn link_down() {
+    let mut graph = parallel_topology();
+    let opt = SpfOpt::default();
+
+    assert_eq!(cost(&spf(&graph, 0, &opt), 2), Some(20));
+    assert_eq!(cost(&spf_reverse(&graph, 2, &opt), 0), Some(20));
+
+    // Only the cheaper of the two parallel links goes down.
+    assert!(link_set_up(&mut graph, &Link::new(0, 1, 10), false));
+    assert_eq!(cost(&spf(&graph, 0, &opt), 2), Some(30));
+    assert_eq!(cost(&spf_reverse(&graph, 2, &opt), 0), Some(30));
+
+    // The other direction is a separate link and is still up.
+    assert_eq!(cost(&spf(&graph, 1, &opt), 0), Some(10));
+
+    assert!(link_set_disabled(&mut graph, &Link::new(0, 1, 20), true));
+    assert_eq!(cost(&spf(&graph, 0, &opt), 2), Some(60));
+    assert_eq!(cost(&spf_reverse(&graph, 2, &opt), 0), Some(60));
+
+    assert!(link_set_up(&mut graph, &Link::new(0, 1, 10), true));
+    assert_eq!(cost(&spf(&graph, 0, &opt), 2), Some(20));
+
+    assert!(!link_set_up(&mut graph, &Link::new(0, 2, 10), false));
+}
+
+#[test]
+pub fn failure_overlay() {
+    let graph = parallel_topology();
+    let opt = SpfOpt::default();
+
+    let failure = Failure::link(&graph, 0, 1);
+    assert_eq!(failure.links.len(), 4);
+
+    let spf = spf_calc_with(&graph, 0, &opt, &SpfDirect::Normal, &failure);
+    assert_eq!(cost(&spf, 2), Some(60));
+    let spf = spf_calc_with(&graph, 2, &opt, &SpfDirect::Reverse, &failure);
+    assert_eq!(cost(&spf, 0), Some(60));
+
+    let spf = spf_calc_with(&graph, 0, &opt, &SpfDirect::Normal, &Failure::node(3));
+    assert_eq!(cost(&spf, 2), Some(20));
+    assert_eq!(cost(&spf, 3), None);
+
+    // The graph itself is left as is.
+    assert_eq!(
+        cost(&spf_calc(&graph, 0, &opt, &SpfDirect::Normal), 2),
+        Some(20)
+    );
+
+    // A failure recorded while a link was down still applies once it is
+    // back up.
+    let mut graph = graph;
+    assert!(link_set_up(&mut graph, &Link::new(0, 1, 10), false));
+    let failure = Failure::link(&graph, 0, 1);
+    assert!(link_set_up(&mut graph, &Link::new(0, 1, 10), true));
+    let spf = spf_calc_with(&graph, 0, &opt, &SpfDirect::Normal, &failure);
+    assert_eq!(cost(&spf, 2), Some(60));
+}
@@ -1,46 +1,15 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::time::Instant;
 
 use spf::*;
 
-//  +---+ +---+ +---+ +---+
-//  | 0 |-| 1 |-|...|-|n-1|
-//  +---+ +---+ +---+ +---+
-//    |     |     |     |
-//  +---+ +---+ +---+ +---+
-//  | n |-|n+1|-|...|-|2n|
-//  +---+ +---+ +---+ +---+
-//    |     |     |     |
-//  +---+ +---+ +---+ +---+
-//  |Xn |-| 1 |-|...|-|   |
-//  +---+ +---+ +---+ +---+
-//
-pub fn matrix_topology(n: usize, opt: &SpfOpt) {
-    let mut graph = BTreeMap::new();
-
-    for i in 0..n {
-        for j in 0..n {
-            let id = (i * n) + j;
-            let mut node = Node::new(&id.to_string(), id);
-
-            // Vertical link: do not create for bottom row
-            if i != n - 1 {
-                let link = Link::new(id, id + n, 10);
-                node.olinks.push(link);
-            }
-
-            // Horizontal link: do not create for rightmost column
-            if j != n - 1 {
-                let link = Link::new(id, id + 1, 10);
-                node.olinks.push(link);
-            }
+mod common;
+use common::*;
 
-            graph.insert(id, node);
-        }
-    }
+pub fn matrix_topology(n: usize, opt: &SpfOpt) {
+    let graph = matrix_graph(n, 1);
 
     let now = Instant::now();
-    // Assuming `spf` now takes a reference to BTreeMap instead of Vec<Node>
     let spf = spf(&graph, 0, opt);
     println!("n:{} {:?}", n, now.elapsed());
 
@@ -69,6 +38,156 @@ pub fn matrix() {
         path_max: 32,
         srv6: false,
         srmpls: true,
+        ..Default::default()
     };
     matrix_topology(100, &opt);
 }
+
+#[test]
+pub fn matrix_dag() {
+    // The DAG keeps every path without a limit, counting them instead of
+    // listing them.
+    let graph = matrix_graph(100, 1);
+
+    let now = Instant::now();
+    let dag = spf_dag(&graph, 0, &SpfDirect::Normal);
+    println!("dag n:100 {:?}", now.elapsed());
+
+    assert_eq!(dag.nodes.len(), 10000);
+    assert_eq!(dag.cost(9999), Some(1980));
+    assert_eq!(dag.nexthops(9999), BTreeSet::from([1, 100]));
+    assert_eq!(dag.path_count(9999), u64::MAX);
+
+    // C(58, 29) paths to the far corner of a 30 x 30 matrix, the first ones
+    // being those kept by a path limit.
+    let graph = matrix_graph(30, 1);
+    let dag = spf_dag(&graph, 0, &SpfDirect::Normal);
+    assert_eq!(dag.path_count(899), 30067266499541040);
+
+    let opt = SpfOpt {
+        full_path: true,
+        path_max: 32,
+        ..Default::default()
+    };
+    let spf = spf(&graph, 0, &opt);
+    assert_eq!(dag.paths(899).take(32).collect::<Vec<_>>(), spf[&899].paths);
+}
+
+// SPF as it was before `SpfQueue`: the queue is a `BTreeMap` keyed by cost
+// and id holding a copy of each reached node's result, updated in place on
+// every equal cost path found.
+fn btree_spf(graph: &Graph, root: usize, opt: &SpfOpt) -> BTreeMap<usize, Path> {
+    let direct = SpfDirect::Normal;
+    let mut spf = BTreeMap::<usize, Path>::new();
+    let mut paths = HashMap::<usize, Path>::new();
+    let mut bt = BTreeMap::<(u32, usize), Path>::new();
+
+    let mut c = Path::new(root);
+    c.paths.push(vec![root]);
+    c.nexthops.insert(vec![root]);
+    paths.insert(root, c.clone());
+    bt.insert((c.cost, root), c);
+
+    while let Some((_, v)) = bt.pop_first() {
+        spf.insert(v.id, v.clone());
+        let Some(node) = graph.get(&v.id) else {
+            continue;
+        };
+        if node.is_disabled {
+            continue;
+        }
+
+        for link in node.links(&direct) {
+            let to = link.id(&direct);
+            if to == root || !link.is_active() || spf.contains_key(&to) {
+                continue;
+            }
+            if graph.get(&to).is_some_and(|x| x.is_disabled) {
+                continue;
+            }
+
+            let c = paths.entry(to).or_insert_with(|| Path::new(to));
+            let ocost = c.cost;
+            let cost = v.cost.saturating_add(link.cost);
+            if c.registered && c.cost < cost {
+                continue;
+            }
+            if !c.registered || c.cost > cost {
+                c.cost = cost;
+                c.paths.clear();
+                c.nexthops.clear();
+            }
+
+            if v.id == root {
+                let path = vec![root, to];
+                if opt.full_path {
+                    c.paths.push(path);
+                } else {
+                    c.nexthops.insert(path);
+                }
+            } else if opt.full_path {
+                for path in &v.paths {
+                    if opt.path_max == 0 || c.paths.len() < opt.path_max {
+                        let mut newpath = path.clone();
+                        newpath.push(to);
+                        c.paths.push(newpath);
+                    }
+                }
+            } else {
+                for nhop in &v.nexthops {
+                    if opt.path_max == 0 || c.nexthops.len() < opt.path_max {
+                        let mut newnhop = nhop.clone();
+                        if nhop.len() < 2 {
+                            newnhop.push(to);
+                        }
+                        c.nexthops.insert(newnhop);
+                    }
+                }
+            }
+
+            if !c.registered {
+                c.registered = true;
+                bt.insert((c.cost, to), c.clone());
+            } else if ocost == c.cost {
+                if let Some(v) = bt.get_mut(&(c.cost, to)) {
+                    v.paths = c.paths.clone();
+                    v.nexthops = c.nexthops.clone();
+                }
+            } else {
+                bt.remove(&(ocost, to));
+                bt.insert((c.cost, to), c.clone());
+            }
+        }
+    }
+    spf
+}
+
+// Run with `make bench`, debug builds are too slow for n = 300.
+#[test]
+#[ignore]
+pub fn matrix_bench() {
+    // BTreeMap, binary heap and radix heap queues on growing matrices.
+    for n in [100, 200, 300] {
+        let graph = matrix_graph(n, 1);
+
+        let now = Instant::now();
+        let btree = btree_spf(&graph, 0, &SpfOpt::default());
+        println!("n:{} btree map {:?}", n, now.elapsed());
+
+        let now = Instant::now();
+        let binary = spf(&graph, 0, &SpfOpt::default());
+        println!("n:{} binary heap {:?}", n, now.elapsed());
+
+        let opt = SpfOpt {
+            radix_heap: true,
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let radix = spf(&graph, 0, &opt);
+        println!("n:{} radix heap {:?}", n, now.elapsed());
+
+        assert_eq!(binary.len(), n * n);
+        assert_eq!(binary, btree);
+        assert_eq!(binary, radix);
+    }
+}
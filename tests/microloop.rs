@@ -0,0 +1,94 @@
+use spf::*;
+
+mod common;
+use common::*;
+
+// Same ring with both directions of the 0 - 1 link down.
+fn ring_failed(n: usize) -> Graph {
+    let mut graph = ring_topology(n);
+    assert!(link_set_up(&mut graph, &Link::new(0, 1, 1), false));
+    assert!(link_set_up(&mut graph, &Link::new(1, 0, 1), false));
+    graph
+}
+
+#[test]
+pub fn microloop_ring5() {
+    let old = ring_topology(5);
+    let new = ring_failed(5);
+
+    // 0 converges first and sends to 4, which still sends back to 0. Toward
+    // 2 the old path of 4 already avoids the failure.
+    let microloops = microloop_calc(&old, &new, 0).unwrap();
+    microloop_print(&new, &microloops);
+    assert_eq!(microloops.keys().copied().collect::<Vec<_>>(), vec![1]);
+
+    let microloop = &microloops[&1];
+    assert_eq!(microloop.loop_nodes, vec![0, 4]);
+    assert_eq!(microloop.pc_path, vec![0, 4, 3, 2, 1]);
+    assert_eq!(microloop.segment_list, vec![SrSegment::NodeSid(3)]);
+
+    // 4 converging before 0 loops as well, its new nexthop 3 is stable.
+    let microloops = microloop_calc(&old, &new, 4).unwrap();
+    assert_eq!(microloops.keys().copied().collect::<Vec<_>>(), vec![1]);
+    assert_eq!(microloops[&1].loop_nodes, vec![4, 0]);
+    assert!(microloops[&1].segment_list.is_empty());
+
+    // Traffic of 3 never reaches the nodes changing their nexthops.
+    assert!(microloop_calc(&old, &new, 3).unwrap().is_empty());
+}
+
+#[test]
+pub fn microloop_ring4() {
+    let old = ring_topology(4);
+    let new = ring_failed(4);
+
+    // 2 was reached over both sides of the ring, so 0 steers to 3 and then
+    // over the 3 - 2 adjacency.
+    let microloops = microloop_calc(&old, &new, 0).unwrap();
+    assert_eq!(microloops.keys().copied().collect::<Vec<_>>(), vec![1]);
+    assert_eq!(microloops[&1].pc_path, vec![0, 3, 2, 1]);
+    assert_eq!(
+        microloops[&1].segment_list,
+        vec![SrSegment::NodeSid(3), SrSegment::AdjSid(3, 2)]
+    );
+}
+
+#[test]
+pub fn microloop_none() {
+    let graph = ring_topology(5);
+    assert!(microloop_calc(&graph, &graph, 0).unwrap().is_empty());
+    assert_eq!(
+        microloop_calc(&graph, &graph, 9),
+        Err(SpfError::UnknownNode(9))
+    );
+}
+
+#[test]
+pub fn microloop_grid() {
+    // A grid has exponentially many shortest paths, stability and loops
+    // are read from the DAGs without enumerating them.
+    let n = 12;
+    let old = grid_graph(n);
+    let mut new = old.clone();
+    assert!(link_set_up(&mut new, &Link::new(0, 1, 1), false));
+    assert!(link_set_up(&mut new, &Link::new(1, 0, 1), false));
+
+    // Nodes of the first row loop between 0 and its bottom neighbor. The
+    // neighbor's other nexthop 13 is stable, 0 has to steer over 12.
+    let microloops = microloop_calc(&old, &new, n).unwrap();
+    assert_eq!(
+        microloops.keys().copied().collect::<Vec<_>>(),
+        (1..n).collect::<Vec<_>>()
+    );
+    assert!(microloops.values().all(|m| m.segment_list.is_empty()));
+
+    let microloops = microloop_calc(&old, &new, 0).unwrap();
+    assert_eq!(microloops.len(), n - 1);
+    for microloop in microloops.values() {
+        assert_eq!(microloop.loop_nodes, vec![0, n]);
+        assert_eq!(
+            microloop.segment_list,
+            vec![SrSegment::NodeSid(n), SrSegment::AdjSid(n, n + 1)]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use spf::*;
+
+//  S ---1--- A ---1--- D
+//  |                   |
+//  1                   10
+//  |                   |
+//  B ---1--- C --------+
+//
+pub fn mpls_topology() -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    let nodes = vec![
+        Node::new("S", 0),
+        Node::new("A", 1),
+        Node::new("B", 2),
+        Node::new("D", 3),
+        Node::new("C", 4),
+    ];
+
+    // Each node has its own SRGB so that the node resolving a label shows
+    // in the label value.
+    for mut node in nodes {
+        node.srgb = Some(Srgb::new(16000 + node.id as u32 * 1000, 1000));
+        node.prefix_sid = Some(100 + node.id as u32);
+        graph.add_node(node);
+    }
+
+    let links = vec![(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 4, 1), (4, 3, 10)];
+
+    for (from, to, cost) in links {
+        for (from, to) in [(from, to), (to, from)] {
+            let mut link = Link::new(from, to, cost);
+            link.adj_sid = Some(24000 + from as u32 * 10 + to as u32);
+            graph.add_link(link);
+        }
+    }
+
+    graph.build()
+}
+
+#[test]
+pub fn label_stack() {
+    let mut graph = mpls_topology();
+    let s = 0;
+    let d = 3;
+    let x = 1;
+
+    let result = tilfa(&graph, s, d, x, &TilfaOpt::default()).unwrap();
+    let path = &result.paths[0];
+    assert_eq!(path.pc_path, vec![0, 2, 4, 3]);
+    assert_eq!(
+        path.repair_list,
+        vec![SrSegment::NodeSid(4), SrSegment::AdjSid(4, 3)]
+    );
+
+    // C's label in B's SRGB, C's Adj-SID toward D, then D's label popped
+    // by C.
+    let stack = mpls_label_stack(&graph, &path.pc_path, &path.repair_list).unwrap();
+    assert_eq!(stack.nexthop, 2);
+    assert_eq!(stack.labels, vec![18104, 24043]);
+
+    graph.get_mut(&d).unwrap().explicit_null = true;
+    let stack = mpls_label_stack(&graph, &path.pc_path, &path.repair_list).unwrap();
+    assert_eq!(
+        stack.labels,
+        vec![18104, 24043, MPLS_LABEL_IPV4_EXPLICIT_NULL]
+    );
+
+    graph.get_mut(&d).unwrap().explicit_null = false;
+    graph.get_mut(&d).unwrap().no_php = true;
+    let stack = mpls_label_stack(&graph, &path.pc_path, &path.repair_list).unwrap();
+    assert_eq!(stack.labels, vec![18104, 24043, 19103]);
+}
+
+#[test]
+pub fn label_stack_first_segment() {
+    let graph = mpls_topology();
+
+    // The neighbor is the first Node-SID, it is popped by S.
+    let repair_list = vec![SrSegment::NodeSid(2), SrSegment::AdjSid(2, 4)];
+    let stack = mpls_label_stack(&graph, &[0, 2, 4, 3], &repair_list).unwrap();
+    assert_eq!(stack.labels, vec![24024, 20103]);
+
+    // S's own Adj-SID only selects the outgoing adjacency.
+    let repair_list = vec![SrSegment::AdjSid(0, 2), SrSegment::AdjSid(2, 4)];
+    let stack = mpls_label_stack(&graph, &[0, 2, 4, 3], &repair_list).unwrap();
+    assert_eq!(stack.nexthop, 2);
+    assert_eq!(stack.labels, vec![24024, 20103]);
+
+    // No repair segment, D's label in the neighbor's SRGB.
+    let stack = mpls_label_stack(&graph, &[0, 1, 3], &[]).unwrap();
+    assert_eq!(stack.labels, vec![17103]);
+}
+
+#[test]
+pub fn label_stack_error() {
+    let mut graph = mpls_topology();
+    let repair_list = vec![SrSegment::NodeSid(4), SrSegment::AdjSid(4, 3)];
+
+    assert_eq!(
+        mpls_label_stack(&graph, &[], &repair_list),
+        Err(SpfError::EmptyPath)
+    );
+    assert_eq!(
+        mpls_label_stack(&graph, &[0], &repair_list),
+        Err(SpfError::InvalidPath(0))
+    );
+    assert_eq!(
+        mpls_label_stack(&graph, &[0, 2, 4, 3], &[SrSegment::AdjSid(0, 1)]),
+        Err(SpfError::InvalidPath(1))
+    );
+    assert_eq!(
+        mpls_label_stack(&graph, &[0, 2, 4, 3], &[SrSegment::AdjSid(1, 3)]),
+        Err(SpfError::InvalidPath(1))
+    );
+
+    // Index out of the SRGB of B.
+    graph.get_mut(&4).unwrap().prefix_sid = Some(1000);
+    assert_eq!(
+        mpls_label_stack(&graph, &[0, 2, 4, 3], &repair_list),
+        Err(SpfError::NoPrefixSid(4))
+    );
+    graph.get_mut(&4).unwrap().prefix_sid = None;
+    assert_eq!(
+        mpls_label_stack(&graph, &[0, 2, 4, 3], &repair_list),
+        Err(SpfError::NoPrefixSid(4))
+    );
+
+    let graph = mpls_topology();
+    let repair_list = vec![SrSegment::NodeSid(4), SrSegment::AdjSid(4, 2)];
+    assert!(mpls_label_stack(&graph, &[0, 2, 4, 3], &repair_list).is_ok());
+    let repair_list = vec![SrSegment::NodeSid(4), SrSegment::AdjSid(4, 0)];
+    assert_eq!(
+        mpls_label_stack(&graph, &[0, 2, 4, 3], &repair_list),
+        Err(SpfError::NoAdjSid(4, 0))
+    );
+}
+
+#[test]
+pub fn tilfa_sr_capable() {
+    let mut graph = mpls_topology();
+    let (s, d, x) = (0, 3, 1);
+    let srmpls = TilfaOpt {
+        srmpls: true,
+        ..Default::default()
+    };
+    let srv6 = TilfaOpt {
+        srv6: true,
+        ..Default::default()
+    };
+
+    // C does not support SR-MPLS, it can neither be the release point nor
+    // process the Adj-SID toward D.
+    graph.get_mut(&4).unwrap().is_srmpls = false;
+    assert_eq!(
+        tilfa(&graph, s, d, x, &srmpls),
+        Err(SpfError::NotSrCapable(4))
+    );
+
+    let result = tilfa(&graph, s, d, x, &srv6).unwrap();
+    assert!(result.p_nodes.contains(&4));
+    assert_eq!(
+        result.paths[0].repair_list,
+        vec![SrSegment::NodeSid(4), SrSegment::AdjSid(4, 3)]
+    );
+
+    // Without a selected dataplane capabilities are not checked.
+    let result = tilfa(&graph, s, d, x, &TilfaOpt::default()).unwrap();
+    assert_eq!(result.paths[0].repair_list.len(), 2);
+
+    // The dataplane selected in the SPF options applies as well.
+    let spf_opt = SpfOpt {
+        srmpls: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        tilfa(&graph, s, d, x, &TilfaOpt::from(&spf_opt)),
+        Err(SpfError::NotSrCapable(4))
+    );
+
+    // B cannot forward C's label, the only repair path is lost.
+    graph.get_mut(&4).unwrap().is_srmpls = true;
+    graph.get_mut(&2).unwrap().is_srmpls = false;
+    assert_eq!(
+        tilfa(&graph, s, d, x, &srmpls),
+        Err(SpfError::NotSrCapable(2))
+    );
+}
@@ -0,0 +1,111 @@
+use spf::*;
+
+//  S ---1--- X ---1--- D
+//  |                   |
+//  N ------ A ------ B ------ C
+//
+// The N-A and A-B links cost 1. The other links of the lower path cost 10
+// toward D and 1 toward S, so that none of N, A, B and C is in P or Q.
+pub fn msd_topology() -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    let nodes = vec![
+        Node::new("S", 0),
+        Node::new("X", 1),
+        Node::new("D", 2),
+        Node::new("N", 3),
+        Node::new("A", 4),
+        Node::new("B", 5),
+        Node::new("C", 6),
+    ];
+    for node in nodes {
+        graph.add_node(node);
+    }
+
+    let links = vec![(0, 1, 1), (1, 2, 1), (3, 4, 1), (4, 5, 1)];
+    for (from, to, cost) in links {
+        graph.add_bidir_link(Link::new(from, to, cost));
+    }
+    let links = vec![(0, 3), (5, 6), (6, 2)];
+    for (from, to) in links {
+        graph.add_link(Link::new(from, to, 10));
+        graph.add_link(Link::new(to, from, 1));
+    }
+
+    graph.build()
+}
+
+#[test]
+pub fn repair_list_minimize() {
+    let graph = msd_topology();
+    let (s, d, x) = (0, 2, 1);
+
+    // Every hop gets an Adj-SID.
+    let result = tilfa(&graph, s, d, x, &TilfaOpt::default()).unwrap();
+    let path = &result.paths[0];
+    assert_eq!(path.pc_path, vec![0, 3, 4, 5, 6, 2]);
+    assert_eq!(
+        path.repair_list,
+        vec![
+            SrSegment::AdjSid(0, 3),
+            SrSegment::AdjSid(3, 4),
+            SrSegment::AdjSid(4, 5),
+            SrSegment::AdjSid(5, 6),
+            SrSegment::AdjSid(6, 2),
+        ]
+    );
+    assert_eq!(repair_list_depth(s, &path.repair_list), 4);
+
+    // N reaches B on its own shortest path.
+    let minimized = vec![
+        SrSegment::AdjSid(0, 3),
+        SrSegment::NodeSid(5),
+        SrSegment::AdjSid(5, 6),
+        SrSegment::AdjSid(6, 2),
+    ];
+    let opt = TilfaOpt {
+        minimize: true,
+        ..Default::default()
+    };
+    let result = tilfa(&graph, s, d, x, &opt).unwrap();
+    assert_eq!(result.paths[0].repair_list, minimized);
+    assert_eq!(
+        minimize_repair_list(&graph, &path.pc_path, &result.failure, &opt),
+        Ok(minimized)
+    );
+    assert_eq!(
+        minimize_repair_list(&graph, &[0], &result.failure, &opt),
+        Err(SpfError::InvalidPath(0))
+    );
+    assert_eq!(
+        minimize_repair_list(&graph, &[], &result.failure, &opt),
+        Err(SpfError::EmptyPath)
+    );
+}
+
+#[test]
+pub fn repair_list_msd() {
+    let mut graph = msd_topology();
+    let (s, d, x) = (0, 2, 1);
+
+    // Exceeding the MSD triggers the minimization.
+    graph.get_mut(&s).unwrap().msd = Some(3);
+    let result = tilfa(&graph, s, d, x, &TilfaOpt::default()).unwrap();
+    assert_eq!(repair_list_depth(s, &result.paths[0].repair_list), 3);
+
+    graph.get_mut(&s).unwrap().msd = Some(2);
+    assert_eq!(
+        tilfa(&graph, s, d, x, &TilfaOpt::default()),
+        Err(SpfError::MsdExceeded(s))
+    );
+
+    // B cannot be the Node-SID target, nor process the Adj-SID toward C.
+    graph.get_mut(&s).unwrap().msd = Some(4);
+    graph.get_mut(&5).unwrap().is_srmpls = false;
+    let opt = TilfaOpt {
+        srmpls: true,
+        minimize: true,
+        ..Default::default()
+    };
+    assert_eq!(tilfa(&graph, s, d, x, &opt), Err(SpfError::NotSrCapable(5)));
+}
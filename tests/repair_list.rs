@@ -170,16 +170,92 @@ pub fn repair() {
         },
     ];
 
-    let repair_list = make_repair_list(&pc_inter1, 0, 100);
+    use SrSegment::*;
+
+    let repair_list = make_repair_list(&pc_inter1, 0, 100).unwrap();
     println!("{:?}", repair_list);
-    let repair_list = make_repair_list(&pc_inter2, 0, 100);
+    assert_eq!(repair_list, vec![NodeSid(3)]);
+
+    let repair_list = make_repair_list(&pc_inter2, 0, 100).unwrap();
     println!("{:?}", repair_list);
-    let repair_list = make_repair_list(&pc_inter3, 0, 100);
+    assert_eq!(repair_list, vec![NodeSid(3), AdjSid(3, 4)]);
+
+    let repair_list = make_repair_list(&pc_inter3, 0, 100).unwrap();
     println!("{:?}", repair_list);
-    let repair_list = make_repair_list(&pc_inter4, 0, 100);
+    assert_eq!(repair_list, vec![NodeSid(3), AdjSid(3, 4), AdjSid(4, 5)]);
+
+    let repair_list = make_repair_list(&pc_inter4, 0, 100).unwrap();
     println!("{:?}", repair_list);
-    let repair_list = make_repair_list(&pc_inter5, 0, 100);
+    assert_eq!(
+        repair_list,
+        vec![NodeSid(3), AdjSid(3, 4), AdjSid(4, 5), AdjSid(5, 100)]
+    );
+
+    let repair_list = make_repair_list(&pc_inter5, 0, 100).unwrap();
     println!("{:?}", repair_list);
-    let repair_list = make_repair_list(&pc_inter6, 0, 100);
+    assert_eq!(
+        repair_list,
+        vec![
+            NodeSid(1),
+            AdjSid(1, 2),
+            AdjSid(2, 3),
+            AdjSid(3, 4),
+            AdjSid(4, 5)
+        ]
+    );
+
+    let repair_list = make_repair_list(&pc_inter6, 0, 100).unwrap();
     println!("{:?}", repair_list);
+    assert_eq!(
+        repair_list,
+        vec![
+            AdjSid(0, 1),
+            AdjSid(1, 2),
+            AdjSid(2, 3),
+            AdjSid(3, 4),
+            AdjSid(4, 5),
+            AdjSid(5, 100)
+        ]
+    );
+}
+
+fn inter(id: usize, p: bool, q: bool) -> Intersect {
+    Intersect { id, p, q }
+}
+
+#[test]
+pub fn repair_unusual() {
+    use SrSegment::*;
+
+    // The whole path is in P space but never reaches Q space.
+    let pc_inter = vec![inter(1, true, false), inter(2, true, false)];
+    assert_eq!(
+        make_repair_list(&pc_inter, 0, 100).unwrap(),
+        vec![NodeSid(2), AdjSid(2, 100)]
+    );
+
+    // The neighbor is a PQ node, plain LFA.
+    let pc_inter = vec![inter(1, true, true), inter(2, true, true)];
+    assert_eq!(make_repair_list(&pc_inter, 0, 100).unwrap(), vec![]);
+
+    // A PQ node in the middle of P space.
+    let pc_inter = vec![
+        inter(1, true, false),
+        inter(2, true, true),
+        inter(3, true, false),
+    ];
+    assert_eq!(
+        make_repair_list(&pc_inter, 0, 100).unwrap(),
+        vec![NodeSid(2)]
+    );
+
+    // D is directly connected.
+    assert_eq!(make_repair_list(&[], 0, 100).unwrap(), vec![]);
+
+    // S and D must have been stripped from the path.
+    let pc_inter = vec![inter(0, true, false), inter(1, false, true)];
+    assert_eq!(
+        make_repair_list(&pc_inter, 0, 100),
+        Err(SpfError::InvalidPath(0))
+    );
 }
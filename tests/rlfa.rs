@@ -0,0 +1,49 @@
+use std::collections::BTreeSet;
+
+use spf::*;
+
+mod common;
+use common::*;
+
+#[test]
+pub fn rlfa_ring6() {
+    let graph = ring_topology(6);
+
+    // A plain LFA cannot protect S-E in an even ring.
+    let lfas = lfa(&graph, 0);
+    assert!(lfas.get(&2).unwrap().is_empty());
+
+    assert_eq!(pq_nodes(&graph, 0, 1), BTreeSet::from([3]));
+
+    let rlfas = rlfa(&graph, 0);
+    for (d, repairs) in &rlfas {
+        println!("{} {:?}", d, repairs);
+    }
+
+    let expect = Rlfa {
+        primary: 1,
+        pq_node: 3,
+        nexthop: 5,
+        cost: 4,
+    };
+    assert_eq!(rlfas.get(&2).unwrap(), &vec![expect]);
+}
+
+#[test]
+pub fn rlfa_ring7() {
+    let graph = ring_topology(7);
+
+    // Node 4 is in the P space of S itself, node 3 only in the P space of
+    // neighbor 6.
+    assert_eq!(pq_nodes(&graph, 0, 1), BTreeSet::from([3, 4]));
+
+    // Both PQ nodes repair D = 2 at cost 5, node 4 is closer to S.
+    let rlfas = rlfa(&graph, 0);
+    let expect = Rlfa {
+        primary: 1,
+        pq_node: 4,
+        nexthop: 6,
+        cost: 5,
+    };
+    assert_eq!(rlfas.get(&2).unwrap(), &vec![expect]);
+}
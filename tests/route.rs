@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use spf::*;
+
+//  0 ---1--- 1 ---1--- 3
+//  |                   |
+//  2                   1
+//  |                   |
+//  2 ---------1--------+
+//
+pub fn route_topology() -> Graph {
+    let mut graph = GraphBuilder::new();
+    for id in 0..4 {
+        graph.add_node(Node::new(&id.to_string(), id));
+    }
+    let links = vec![(0, 1, 1), (0, 2, 2), (1, 3, 1), (2, 3, 1)];
+    for (from, to, cost) in links {
+        graph.add_bidir_link(Link::new(from, to, cost));
+    }
+    graph.build()
+}
+
+fn prefix(s: &str) -> Prefix {
+    let (addr, len) = s.split_once('/').unwrap();
+    Prefix::new(addr.parse().unwrap(), len.parse().unwrap())
+}
+
+fn route(cost: u32, nodes: &[usize], nexthops: &[usize]) -> Route {
+    Route {
+        cost,
+        nodes: nodes.iter().copied().collect(),
+        nexthops: nexthops.iter().copied().collect(),
+    }
+}
+
+#[test]
+pub fn prefix_new() {
+    assert_eq!(prefix("10.1.2.3/24"), prefix("10.1.2.0/24"));
+    assert_eq!(prefix("10.1.2.3/24").to_string(), "10.1.2.0/24");
+    assert_eq!(prefix("10.1.2.3/40").len, 32);
+    assert_eq!(prefix("2001:db8::1/32").to_string(), "2001:db8::/32");
+    assert_eq!(prefix("0.0.0.0/0").to_string(), "0.0.0.0/0");
+}
+
+#[test]
+pub fn route_prc() {
+    let mut graph = route_topology();
+    let (p0, p2, p3) = (
+        prefix("10.0.0.0/24"),
+        prefix("10.0.2.0/24"),
+        prefix("2001:db8:3::/48"),
+    );
+    graph.get_mut(&0).unwrap().prefixes.insert(p0, 0);
+    graph.get_mut(&2).unwrap().prefixes.insert(p2, 10);
+    graph.get_mut(&3).unwrap().prefixes.insert(p3, 1);
+
+    let opt = SpfOpt::default();
+    let spf = spf(&graph, 0, &opt);
+    let mut rib = route_calc(&graph, &spf);
+    assert_eq!(
+        rib,
+        BTreeMap::from([
+            (p0, route(0, &[0], &[])),
+            (p2, route(12, &[2], &[2])),
+            (p3, route(3, &[3], &[1])),
+        ])
+    );
+
+    // Node 1 advertises P2 too and becomes the closest advertiser, node 3
+    // withdraws P3.
+    graph.get_mut(&1).unwrap().prefixes.insert(p2, 5);
+    graph.get_mut(&3).unwrap().prefixes.remove(&p3);
+    let p1 = prefix("10.0.1.0/24");
+    graph.get_mut(&1).unwrap().prefixes.insert(p1, 1);
+
+    let diff = prc_calc(&graph, &spf, &mut rib, &BTreeSet::from([p1, p2, p3]));
+    assert_eq!(diff.added, BTreeMap::from([(p1, route(2, &[1], &[1]))]));
+    assert_eq!(diff.changed, BTreeMap::from([(p2, route(6, &[1], &[1]))]));
+    assert_eq!(diff.removed, BTreeSet::from([p3]));
+
+    // The partial calculation ends up with the full one.
+    assert_eq!(rib, route_calc(&graph, &spf));
+
+    let diff = prc_calc(&graph, &spf, &mut rib, &BTreeSet::from([p0, p1]));
+    assert!(diff.is_empty());
+
+    // Full path mode gives the same nexthops.
+    let opt = SpfOpt {
+        full_path: true,
+        ..Default::default()
+    };
+    assert_eq!(route_calc(&graph, &spf::spf(&graph, 0, &opt)), rib);
+}
+
+#[test]
+pub fn route_anycast() {
+    let mut graph = route_topology();
+    let anycast = prefix("192.0.2.0/24");
+    let v6 = prefix("2001:db8::/32");
+
+    // Nodes 1 and 2 are at the same total cost, node 3 further away.
+    graph.get_mut(&1).unwrap().prefixes.insert(anycast, 2);
+    graph.get_mut(&2).unwrap().prefixes.insert(anycast, 1);
+    graph.get_mut(&3).unwrap().prefixes.insert(anycast, 2);
+    graph.get_mut(&3).unwrap().prefixes.insert(v6, 0);
+
+    let spf = spf(&graph, 0, &SpfOpt::default());
+    let rib = route_calc(&graph, &spf);
+    route_table_print(&graph, &rib);
+    assert_eq!(rib[&anycast], route(3, &[1, 2], &[1, 2]));
+    assert_eq!(rib[&v6], route(2, &[3], &[1]));
+
+    // The root advertising the prefix makes it local.
+    graph.get_mut(&0).unwrap().prefixes.insert(anycast, 3);
+    let rib = route_calc(&graph, &spf);
+    assert_eq!(rib[&anycast], route(3, &[0, 1, 2], &[]));
+    graph.get_mut(&0).unwrap().prefixes.insert(anycast, 0);
+    let rib = route_calc(&graph, &spf);
+    assert_eq!(rib[&anycast], route(0, &[0], &[]));
+}
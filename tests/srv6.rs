@@ -0,0 +1,140 @@
+use std::net::Ipv6Addr;
+
+use spf::*;
+
+//  S ---1--- A ---1--- D
+//  |                   |
+//  1                   10
+//  |                   |
+//  B ---1--- C --------+
+//
+pub fn srv6_topology() -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    let nodes = vec![
+        Node::new("S", 0),
+        Node::new("A", 1),
+        Node::new("B", 2),
+        Node::new("D", 3),
+        Node::new("C", 4),
+    ];
+
+    // Node N owns locator fc00:0:N::/48, End is function 1 and End.X
+    // toward M is function 0x100 + M.
+    for mut node in nodes {
+        let locator = Srv6Locator::new(Ipv6Addr::new(0xfc00, 0, node.id as u16, 0, 0, 0, 0, 0), 48);
+        node.end_sid = Some(locator.sid(1));
+        node.srv6_locator = Some(locator);
+        graph.add_node(node);
+    }
+
+    let links = vec![(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 4, 1), (4, 3, 10)];
+
+    for (from, to, cost) in links {
+        for (from, to) in [(from, to), (to, from)] {
+            let locator =
+                Srv6Locator::new(Ipv6Addr::new(0xfc00, 0, from as u16, 0, 0, 0, 0, 0), 48);
+            let mut link = Link::new(from, to, cost);
+            link.end_x_sid = Some(locator.sid(0x100 + to as u128));
+            graph.add_link(link);
+        }
+    }
+
+    graph.build()
+}
+
+fn sid(s: &str) -> Ipv6Addr {
+    s.parse().unwrap()
+}
+
+#[test]
+pub fn locator() {
+    let locator = Srv6Locator::new(sid("fc00:0:1::"), 48);
+    assert_eq!(locator.sid(1), sid("fc00:0:1::1"));
+    assert_eq!(locator.sid(0x1_0000), sid("fc00:0:1::1:0"));
+    assert!(locator.contains(&sid("fc00:0:1:ffff::")));
+    assert!(!locator.contains(&sid("fc00:0:2::1")));
+}
+
+#[test]
+pub fn sid_list() {
+    let mut graph = srv6_topology();
+
+    let result = tilfa(&graph, 0, 3, 1, &TilfaOpt::default()).unwrap();
+    let path = &result.paths[0];
+    assert_eq!(path.pc_path, vec![0, 2, 4, 3]);
+
+    // The End.X SID of R4 reaches R4 through its locator, no End SID is
+    // needed before it.
+    let sids = srv6_sid_list(&graph, &path.pc_path, &path.repair_list).unwrap();
+    assert_eq!(sids, vec![sid("fc00:0:4::103")]);
+
+    let node_sid = vec![SrSegment::NodeSid(4)];
+    let sids = srv6_sid_list(&graph, &path.pc_path, &node_sid).unwrap();
+    assert_eq!(sids, vec![sid("fc00:0:4::1")]);
+
+    // A leading End.X SID of S only selects the outgoing adjacency.
+    let repair_list = vec![SrSegment::AdjSid(0, 2), SrSegment::AdjSid(2, 4)];
+    let sids = srv6_sid_list(&graph, &[0, 2, 4, 3], &repair_list).unwrap();
+    assert_eq!(sids, vec![sid("fc00:0:2::104")]);
+
+    graph.get_mut(&4).unwrap().is_srv6 = false;
+    assert_eq!(
+        srv6_sid_list(&graph, &path.pc_path, &node_sid),
+        Err(SpfError::NoEndSid(4))
+    );
+    assert_eq!(
+        srv6_sid_list(&graph, &path.pc_path, &path.repair_list),
+        Err(SpfError::NoEndXSid(4, 3))
+    );
+}
+
+#[test]
+pub fn sid_list_error() {
+    let mut graph = srv6_topology();
+
+    assert_eq!(
+        srv6_sid_list(&graph, &[0], &[]),
+        Err(SpfError::InvalidPath(0))
+    );
+    assert_eq!(srv6_sid_list(&graph, &[], &[]), Err(SpfError::EmptyPath));
+
+    let repair_list = vec![SrSegment::NodeSid(2), SrSegment::AdjSid(1, 3)];
+    assert_eq!(
+        srv6_sid_list(&graph, &[0, 2, 4, 3], &repair_list),
+        Err(SpfError::InvalidPath(1))
+    );
+
+    let repair_list = vec![SrSegment::NodeSid(4), SrSegment::AdjSid(4, 3)];
+    for link in &mut graph.get_mut(&4).unwrap().olinks {
+        link.end_x_sid = None;
+    }
+    assert_eq!(
+        srv6_sid_list(&graph, &[0, 2, 4, 3], &repair_list),
+        Err(SpfError::NoEndXSid(4, 3))
+    );
+
+    // SIDs must belong to the locator of their node.
+    let mut graph = srv6_topology();
+    let repair_list = vec![SrSegment::NodeSid(4), SrSegment::AdjSid(4, 3)];
+    graph.get_mut(&4).unwrap().end_sid = Some(sid("fc00:0:5::1"));
+    assert_eq!(
+        srv6_sid_list(&graph, &[0, 2, 4, 3], &[SrSegment::NodeSid(4)]),
+        Err(SpfError::SidOutsideLocator(4))
+    );
+
+    let mut graph = srv6_topology();
+    for link in &mut graph.get_mut(&4).unwrap().olinks {
+        link.end_x_sid = Some(sid("fc00:0:5::103"));
+    }
+    assert_eq!(
+        srv6_sid_list(&graph, &[0, 2, 4, 3], &repair_list),
+        Err(SpfError::SidOutsideLocator(4))
+    );
+
+    graph.get_mut(&4).unwrap().srv6_locator = None;
+    assert_eq!(
+        srv6_sid_list(&graph, &[0, 2, 4, 3], &repair_list),
+        Err(SpfError::NoEndXSid(4, 3))
+    );
+}
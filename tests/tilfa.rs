@@ -1,9 +1,12 @@
-use std::collections::BTreeMap;
+use std::collections::HashSet;
 
 use spf::*;
 
-pub fn tilfa_graph() -> BTreeMap<usize, Node> {
-    let mut graph = BTreeMap::new();
+mod common;
+use common::*;
+
+pub fn tilfa_graph() -> Graph {
+    let mut graph = GraphBuilder::new();
 
     // Insert nodes
     let nodes = vec![
@@ -18,7 +21,7 @@ pub fn tilfa_graph() -> BTreeMap<usize, Node> {
     ];
 
     for node in nodes {
-        graph.insert(node.id, node);
+        graph.add_node(node);
     }
 
     // Define links
@@ -57,19 +60,10 @@ pub fn tilfa_graph() -> BTreeMap<usize, Node> {
 
     // Insert links into nodes
     for (from, to, cost) in links {
-        graph
-            .get_mut(&from)
-            .unwrap()
-            .olinks
-            .push(Link::new(from, to, cost));
-        graph
-            .get_mut(&to)
-            .unwrap()
-            .ilinks
-            .push(Link::new(from, to, cost));
+        graph.add_link(Link::new(from, to, cost));
     }
 
-    graph
+    graph.build()
 }
 
 #[test]
@@ -79,11 +73,30 @@ pub fn tilfa1() {
     let d = 7;
     let x = 1;
 
-    tilfa(&graph, s, d, x);
+    let opt = TilfaOpt {
+        protection: Protection::Node,
+        ..Default::default()
+    };
+    let result = tilfa(&graph, s, d, x, &opt).unwrap();
+    tilfa_print(&graph, &result);
+
+    assert_eq!(result.p_nodes, HashSet::from([2, 3])); // N2 N3
+    assert_eq!(result.q_nodes, HashSet::from([6])); // R3
+    assert_eq!(result.paths.len(), 1);
+    assert_eq!(result.paths[0].pc_path, vec![0, 2, 4, 5, 6, 7]);
+    assert_eq!(
+        result.paths[0].repair_list,
+        vec![
+            SrSegment::NodeSid(2),
+            SrSegment::AdjSid(2, 4),
+            SrSegment::AdjSid(4, 5),
+            SrSegment::AdjSid(5, 6),
+        ]
+    );
 }
 
-pub fn tilfa_graph_adj_seg() -> BTreeMap<usize, Node> {
-    let mut graph = BTreeMap::new();
+pub fn tilfa_graph_adj_seg() -> Graph {
+    let mut graph = GraphBuilder::new();
 
     // Insert nodes explicitly
     let nodes = vec![
@@ -100,7 +113,7 @@ pub fn tilfa_graph_adj_seg() -> BTreeMap<usize, Node> {
     ];
 
     for node in nodes {
-        graph.insert(node.id, node);
+        graph.add_node(node);
     }
 
     // Define and add links
@@ -145,19 +158,10 @@ pub fn tilfa_graph_adj_seg() -> BTreeMap<usize, Node> {
 
     // Insert links into nodes
     for (from, to, cost) in links {
-        graph
-            .get_mut(&from)
-            .unwrap()
-            .olinks
-            .push(Link::new(from, to, cost));
-        graph
-            .get_mut(&to)
-            .unwrap()
-            .ilinks
-            .push(Link::new(from, to, cost));
+        graph.add_link(Link::new(from, to, cost));
     }
 
-    graph
+    graph.build()
 }
 
 #[test]
@@ -167,5 +171,370 @@ pub fn tilfa2() {
     let d = 9; // D
     let x = 2; // R3
 
-    tilfa(&graph, s, d, x);
+    // R3 is not a neighbor of S, so tilfa() rejects it. The failure of R3
+    // is still repairable.
+    let opt = TilfaOpt {
+        protection: Protection::Node,
+        ..Default::default()
+    };
+    assert_eq!(
+        tilfa(&graph, s, d, x, &opt),
+        Err(SpfError::NotAdjacent(s, x))
+    );
+    let result = tilfa_failure(&graph, s, d, &Failure::node(x), &opt).unwrap();
+    tilfa_print(&graph, &result);
+
+    assert_eq!(result.p_nodes, HashSet::from([1])); // R2
+    assert_eq!(result.q_nodes, HashSet::from([3, 4])); // R4 R5
+    assert_eq!(result.paths.len(), 1);
+    assert_eq!(result.paths[0].pc_path, vec![0, 1, 5, 6, 3, 4, 9]);
+    assert_eq!(
+        result.paths[0]
+            .intersects
+            .iter()
+            .map(|inter| inter.id)
+            .collect::<Vec<_>>(),
+        vec![1, 5, 6, 3, 4]
+    );
+    assert_eq!(
+        result.paths[0].repair_list,
+        vec![
+            SrSegment::NodeSid(1),
+            SrSegment::AdjSid(1, 5),
+            SrSegment::AdjSid(5, 6),
+            SrSegment::AdjSid(6, 3),
+        ]
+    );
+}
+
+#[test]
+pub fn pq_space() {
+    let graph = tilfa_graph();
+
+    // R1 is reached from S over ECMP via N1 and N2, so it is not in the P
+    // space of S with respect to N1.
+    assert_eq!(
+        p_space_nodes(&graph, 0, &Failure::node(1)),
+        HashSet::from([2, 3])
+    );
+    assert_eq!(
+        q_space_nodes(&graph, 7, &Failure::node(1)),
+        HashSet::from([6])
+    );
+
+    // Protecting N2 instead leaves everything but R1 reachable.
+    assert_eq!(
+        p_space_nodes(&graph, 0, &Failure::node(2)),
+        HashSet::from([1, 3, 5, 6, 7])
+    );
+
+    let graph = tilfa_graph_adj_seg();
+    assert_eq!(
+        p_space_nodes(&graph, 0, &Failure::node(2)),
+        HashSet::from([1])
+    );
+    assert_eq!(
+        q_space_nodes(&graph, 9, &Failure::node(2)),
+        HashSet::from([3, 4])
+    );
+}
+
+#[test]
+pub fn extended_p_space() {
+    let graph = tilfa_graph_adj_seg();
+    let x = 2; // R3
+
+    // R4 reaches R9 and R10 only through R3, but its neighbor R8 does not.
+    assert_eq!(
+        p_space_nodes(&graph, 3, &Failure::node(x)),
+        HashSet::from([4, 9])
+    );
+    assert_eq!(
+        extended_p_space_nodes(&graph, 3, &Failure::node(x)),
+        HashSet::from([4, 6, 7, 8, 9])
+    );
+
+    // Every shortest path from R7 transits R3, so its P space is empty.
+    assert!(p_space_nodes(&graph, 5, &Failure::node(x)).is_empty());
+    assert_eq!(
+        extended_p_space_nodes(&graph, 5, &Failure::node(x)),
+        HashSet::from([0, 1, 6, 7, 8])
+    );
+
+    // R8 is in the extended P space of R7 but only reached over the R7-R8
+    // adjacency, the repair list does not change.
+    let opt = TilfaOpt {
+        protection: Protection::Node,
+        ..Default::default()
+    };
+    let result = tilfa(&graph, 5, 9, x, &opt).unwrap();
+    assert_eq!(
+        result.paths[0].repair_list,
+        vec![SrSegment::AdjSid(5, 6), SrSegment::AdjSid(6, 3)]
+    );
+    let base = coverage_total(&tilfa_coverage(&graph, &opt));
+
+    let opt = TilfaOpt {
+        extended_p_space: true,
+        protection: Protection::Node,
+        ..Default::default()
+    };
+    let result = tilfa(&graph, 5, 9, x, &opt).unwrap();
+    assert_eq!(
+        result.paths[0].repair_list,
+        vec![SrSegment::AdjSid(5, 6), SrSegment::AdjSid(6, 3)]
+    );
+
+    // Toward S the first hop R2 is in the extended P space and in the Q
+    // space, so the Adj-SID steering R7 to it gives way to a plain LFA.
+    let result = tilfa(&graph, 5, 0, x, &TilfaOpt::new()).unwrap();
+    assert_eq!(result.paths[0].repair_list, vec![SrSegment::AdjSid(5, 1)]);
+    let result = tilfa(&graph, 5, 0, x, &opt).unwrap();
+    assert!(result.paths[0].repair_list.is_empty());
+
+    // The coverage does not change on this graph: for every (S, X, D)
+    // tuple, the only repairs the extended P space changes are those
+    // whose sole segment was the Adj-SID of S toward the first hop, which
+    // S does not push. No neighbor's P space holds a release point
+    // further along a path that still needs a Node-SID or Adj-SID.
+    assert_eq!(coverage_total(&tilfa_coverage(&graph, &opt)), base);
+}
+
+fn bidir_graph(names: &[&str], links: &[(usize, usize, u32)]) -> Graph {
+    let mut graph = GraphBuilder::new();
+    for (id, name) in names.iter().enumerate() {
+        graph.add_node(Node::new(name, id));
+    }
+    for (from, to, cost) in links {
+        graph.add_bidir_link(Link::new(*from, *to, *cost));
+    }
+    graph.build()
+}
+
+#[test]
+pub fn extended_p_space_first_hop() {
+    use SrSegment::*;
+
+    let opt = TilfaOpt {
+        extended_p_space: true,
+        ..Default::default()
+    };
+
+    // P is in the P space of M, but S repairs through A whose shortest path
+    // to P goes back over S-E.
+    let graph = bidir_graph(
+        &["S", "E", "A", "P", "M", "D"],
+        &[
+            (0, 1, 1),
+            (0, 2, 1),
+            (1, 3, 1),
+            (2, 3, 10),
+            (0, 4, 20),
+            (4, 3, 1),
+            (1, 5, 1),
+            (3, 5, 1),
+        ],
+    );
+    assert!(extended_p_space_nodes(&graph, 0, &Failure::link(&graph, 0, 1)).contains(&3));
+    assert_eq!(
+        p_space_nodes(&graph, 2, &Failure::link(&graph, 0, 1)),
+        HashSet::from([0])
+    );
+
+    for opt in [&TilfaOpt::default(), &opt] {
+        let result = tilfa(&graph, 0, 5, 1, opt).unwrap();
+        assert_eq!(result.paths[0].pc_path, vec![0, 2, 3, 5]);
+        assert_eq!(result.paths[0].repair_list, vec![NodeSid(2), AdjSid(2, 3)]);
+    }
+
+    // B is reached from S over ECMP through E but is in the P space of A,
+    // the first hop.
+    let graph = bidir_graph(
+        &["S", "E", "A", "B", "D"],
+        &[(0, 1, 1), (0, 2, 1), (2, 3, 1), (3, 1, 1), (1, 4, 1)],
+    );
+    let result = tilfa(&graph, 0, 4, 1, &TilfaOpt::default()).unwrap();
+    assert_eq!(result.paths[0].pc_path, vec![0, 2, 3, 1, 4]);
+    assert_eq!(result.paths[0].repair_list, vec![NodeSid(2), AdjSid(2, 3)]);
+
+    let result = tilfa(&graph, 0, 4, 1, &opt).unwrap();
+    assert_eq!(result.paths[0].repair_list, vec![AdjSid(0, 2), NodeSid(3)]);
+}
+
+#[test]
+pub fn tilfa_link() {
+    let graph = tilfa_graph();
+    let s = 0;
+    let d = 7;
+    let x = 1;
+
+    // Only the S-N1 adjacency fails, so traffic may still go through N1.
+    let result = tilfa(&graph, s, d, x, &TilfaOpt::default()).unwrap();
+    tilfa_print(&graph, &result);
+
+    assert_eq!(result.failure, Failure::link(&graph, s, x));
+    assert_eq!(result.p_nodes, HashSet::from([2, 3])); // N2 N3
+    assert_eq!(result.q_nodes, HashSet::from([1, 4, 5, 6])); // N1 R1 R2 R3
+    assert_eq!(result.paths.len(), 1);
+    assert_eq!(result.paths[0].pc_path, vec![0, 2, 4, 1, 7]);
+    assert_eq!(
+        result.paths[0].repair_list,
+        vec![SrSegment::NodeSid(2), SrSegment::AdjSid(2, 4)]
+    );
+}
+
+#[test]
+pub fn tilfa_node_fallback() {
+    let graph = tilfa_graph();
+    let opt = TilfaOpt {
+        protection: Protection::Node,
+        ..Default::default()
+    };
+
+    // D is the protected neighbor itself, node protection is impossible.
+    let result = tilfa(&graph, 0, 1, 1, &opt).unwrap();
+    assert_eq!(result.failure, Failure::link(&graph, 0, 1));
+    assert_eq!(result.paths[0].pc_path, vec![0, 2, 4, 1]);
+
+    // A single failed link among other working ones.
+    let mut failure = Failure::new();
+    failure.add_link(Link::new(0, 2, 1));
+    let result = tilfa_failure(&graph, 0, 4, &failure, &opt).unwrap();
+    assert_eq!(result.paths[0].pc_path, vec![0, 1, 4]);
+    assert!(result.paths[0].repair_list.is_empty());
+    // Only a neighbor of S can be protected.
+    assert_eq!(
+        tilfa(&graph, 0, 7, 4, &opt),
+        Err(SpfError::NotAdjacent(0, 4))
+    );
+
+    // Avoiding N1 needs more SIDs than S can push, the link protecting
+    // repair over N2-R1 fits.
+    let mut graph = graph;
+    graph.get_mut(&0).unwrap().msd = Some(2);
+    let result = tilfa(&graph, 0, 7, 1, &opt).unwrap();
+    assert_eq!(result.failure, Failure::link(&graph, 0, 1));
+    assert_eq!(result.paths[0].pc_path, vec![0, 2, 4, 1, 7]);
+}
+
+#[test]
+pub fn tilfa_srlg() {
+    let mut graph = tilfa_graph();
+    let s = 0;
+    let d = 7;
+    let x = 1;
+
+    // S-N1 and N2-R1 share fate.
+    for node in graph.values_mut() {
+        for link in node.olinks.iter_mut().chain(node.ilinks.iter_mut()) {
+            if matches!((link.from, link.to), (0, 1) | (1, 0) | (2, 4) | (4, 2)) {
+                link.srlgs.push(100);
+            }
+        }
+    }
+
+    let failure = Failure::srlg(&graph, s, x);
+    assert_eq!(failure.links.len(), 4);
+
+    // Link protection alone repairs over N2-R1.
+    let result = tilfa(&graph, s, d, x, &TilfaOpt::default()).unwrap();
+    assert_eq!(result.paths[0].pc_path, vec![0, 2, 4, 1, 7]);
+
+    let opt = TilfaOpt {
+        protection: Protection::Srlg,
+        ..Default::default()
+    };
+    let result = tilfa(&graph, s, d, x, &opt).unwrap();
+    tilfa_print(&graph, &result);
+
+    assert_eq!(result.failure, failure);
+    assert_eq!(result.paths.len(), 1);
+    assert_eq!(result.paths[0].pc_path, vec![0, 3, 4, 1, 7]);
+    assert_eq!(
+        result.paths[0].repair_list,
+        vec![SrSegment::NodeSid(3), SrSegment::AdjSid(3, 4)]
+    );
+}
+
+#[test]
+pub fn tilfa_error() {
+    let mut graph = tilfa_graph();
+    let opt = TilfaOpt::default();
+
+    assert_eq!(tilfa(&graph, 0, 7, 8, &opt), Err(SpfError::UnknownNode(8)));
+    assert_eq!(tilfa(&graph, 0, 9, 1, &opt), Err(SpfError::UnknownNode(9)));
+    assert_eq!(
+        tilfa(&graph, 0, 7, 4, &opt),
+        Err(SpfError::NotAdjacent(0, 4))
+    );
+
+    // N2 hangs off S and R1 only, it has no repair path toward N2 once both
+    // R1 and the S-N2 adjacency are gone.
+    let mut failure = Failure::node(4);
+    failure.add_link(Link::new(0, 2, 1));
+    assert_eq!(
+        tilfa_failure(&graph, 0, 2, &failure, &opt),
+        Err(SpfError::NoRepairPath(2))
+    );
+    assert_eq!(
+        pc_paths(&graph, 0, 2, &failure),
+        Err(SpfError::NoRepairPath(2))
+    );
+
+    // R4 is a cut vertex toward D, node protection is impossible and link
+    // protection is used instead.
+    let adj_seg = tilfa_graph_adj_seg();
+    let node = TilfaOpt {
+        protection: Protection::Node,
+        ..Default::default()
+    };
+    let result = tilfa(&adj_seg, 2, 9, 3, &node).unwrap();
+    assert_eq!(result.failure, Failure::link(&adj_seg, 2, 3));
+    assert_eq!(result.paths[0].pc_path, vec![2, 6, 3, 4, 9]);
+
+    // S protecting itself is rejected, minimized or not.
+    let minimize = TilfaOpt {
+        minimize: true,
+        ..Default::default()
+    };
+    for opt in [&opt, &minimize] {
+        assert_eq!(
+            tilfa(&graph, 0, 0, 1, opt),
+            Err(SpfError::RootIsDestination(0))
+        );
+    }
+    assert_eq!(
+        pc_paths(&graph, 0, 0, &Failure::node(1)),
+        Err(SpfError::RootIsDestination(0))
+    );
+
+    // Isolate D.
+    graph.get_mut(&7).unwrap().is_disabled = true;
+    assert_eq!(tilfa(&graph, 0, 7, 1, &opt), Err(SpfError::Unreachable(7)));
+
+    graph.get_mut(&0).unwrap().is_disabled = true;
+    assert_eq!(tilfa(&graph, 0, 6, 1, &opt), Err(SpfError::RootDisabled(0)));
+}
+
+#[test]
+pub fn tilfa_grid() {
+    // Corner to corner the grid has C(78, 39) shortest paths, P and Q space
+    // must not enumerate them.
+    let n = 40;
+    let graph = grid_graph(n);
+    let d = n * n - 1;
+    let failure = Failure::link(&graph, 0, 1);
+
+    // Every node off the first column may be reached through 1.
+    let p_nodes = p_space_nodes(&graph, 0, &failure);
+    assert_eq!(p_nodes, (1..n).map(|i| i * n).collect::<HashSet<_>>());
+
+    let q_nodes = q_space_nodes(&graph, d, &failure);
+    assert_eq!(q_nodes.len(), n * n - 2);
+    assert!(!q_nodes.contains(&0));
+
+    let result = tilfa(&graph, 0, d, 1, &TilfaOpt::default()).unwrap();
+    assert_eq!(result.paths.len(), 1);
+    assert_eq!(result.paths[0].pc_path[1], n);
+    assert!(result.paths[0].repair_list.is_empty());
 }
@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use spf::*;
+
+mod common;
+use common::*;
+
+//        +--- A ---+--- C ---+
+//        |         |         |
+//  S ----+         +--- E ---+--- D
+//        |                   |
+//        +--- B -------------+
+//
+// Every link costs 1 except B-D, which costs 2.
+pub fn ucmp_topology() -> Graph {
+    let mut graph = GraphBuilder::new();
+
+    let nodes = vec![
+        Node::new("S", 0),
+        Node::new("A", 1),
+        Node::new("B", 2),
+        Node::new("C", 3),
+        Node::new("E", 4),
+        Node::new("D", 5),
+    ];
+    for node in nodes {
+        graph.add_node(node);
+    }
+
+    let links = vec![
+        (0, 1, 1, 10),
+        (0, 2, 1, 40),
+        (1, 3, 1, 10),
+        (1, 4, 1, 10),
+        (3, 5, 1, 10),
+        (4, 5, 1, 10),
+        (2, 5, 2, 10),
+    ];
+    for (from, to, cost, bandwidth) in links {
+        let mut link = Link::new(from, to, cost);
+        link.bandwidth = Some(bandwidth);
+        graph.add_bidir_link(link);
+    }
+
+    graph.build()
+}
+
+#[test]
+pub fn ucmp_bandwidth() {
+    let mut graph = ucmp_topology();
+    let spf = spf(&graph, 0, &SpfOpt::default());
+
+    let ucmp = ucmp_calc(&graph, 0, &spf, &UcmpOpt::default());
+    assert_eq!(ucmp[&5], BTreeMap::from([(1, 1), (2, 4)]));
+    assert_eq!(ucmp[&3], BTreeMap::from([(1, 1)]));
+    assert!(!ucmp.contains_key(&0));
+
+    // The weighted nexthops are the ECMP ones.
+    for (id, path) in &spf {
+        let nexthops = path
+            .nexthops
+            .iter()
+            .filter_map(|nhop| nhop.get(1).copied())
+            .collect::<Vec<_>>();
+        if *id != 0 {
+            let mut nexthops = nexthops;
+            nexthops.sort();
+            assert_eq!(ucmp[id].keys().copied().collect::<Vec<_>>(), nexthops);
+        }
+    }
+
+    // A parallel link adds its bandwidth.
+    let mut link = Link::new(0, 1, 1);
+    link.bandwidth = Some(30);
+    link.srlgs.push(1);
+    let mut builder = GraphBuilder::new();
+    for node in graph.values() {
+        builder.add_node(node.clone());
+    }
+    for node in graph.values() {
+        for link in &node.olinks {
+            builder.add_link(link.clone());
+        }
+    }
+    builder.add_bidir_link(link.clone());
+    graph = builder.build();
+    let spf = spf::spf(&graph, 0, &SpfOpt::default());
+    let ucmp = ucmp_calc(&graph, 0, &spf, &UcmpOpt::default());
+    assert_eq!(ucmp[&5], BTreeMap::from([(1, 1), (2, 1)]));
+
+    // Down links do not count.
+    link_set_up(&mut graph, &link, false);
+    let ucmp = ucmp_calc(&graph, 0, &spf, &UcmpOpt::default());
+    assert_eq!(ucmp[&5], BTreeMap::from([(1, 1), (2, 4)]));
+}
+
+#[test]
+pub fn ucmp_path_count() {
+    let mut graph = ucmp_topology();
+    let spf = spf(&graph, 0, &SpfOpt::default());
+
+    // A carries two paths toward D, B one.
+    let opt = UcmpOpt { path_count: true };
+    let ucmp = ucmp_calc(&graph, 0, &spf, &opt);
+    assert_eq!(ucmp[&5], BTreeMap::from([(1, 1), (2, 2)]));
+
+    // Without bandwidth every link weighs the same.
+    for node in graph.values_mut() {
+        for link in node.olinks.iter_mut().chain(node.ilinks.iter_mut()) {
+            link.bandwidth = None;
+        }
+    }
+    let ucmp = ucmp_calc(&graph, 0, &spf, &opt);
+    assert_eq!(ucmp[&5], BTreeMap::from([(1, 2), (2, 1)]));
+    let ucmp = ucmp_calc(&graph, 0, &spf, &UcmpOpt::default());
+    assert_eq!(ucmp[&5], BTreeMap::from([(1, 1), (2, 1)]));
+}
+
+#[test]
+pub fn ucmp_overflow() {
+    // Far more shortest paths toward the opposite corner than a u64 holds.
+    let graph = grid_graph(40);
+    let spf = spf(&graph, 0, &SpfOpt::default());
+    let corner = 40 * 40 - 1;
+
+    let ucmp = ucmp_calc(&graph, 0, &spf, &UcmpOpt::default());
+    assert_eq!(ucmp[&corner], BTreeMap::from([(1, 1), (40, 1)]));
+
+    let opt = UcmpOpt { path_count: true };
+    let ucmp = ucmp_calc(&graph, 0, &spf, &opt);
+    assert_eq!(ucmp[&corner], BTreeMap::from([(1, 1), (40, 1)]));
+    assert_eq!(ucmp[&(corner - 1)], BTreeMap::from([(1, 1), (40, 1)]));
+}
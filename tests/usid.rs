@@ -0,0 +1,90 @@
+use std::net::Ipv6Addr;
+
+use spf::*;
+
+fn sid(s: &str) -> Ipv6Addr {
+    s.parse().unwrap()
+}
+
+fn sids(list: &[&str]) -> Vec<Ipv6Addr> {
+    list.iter().map(|s| sid(s)).collect()
+}
+
+#[test]
+pub fn usid_pack() {
+    let format = UsidFormat::F3216;
+    assert_eq!(format.capacity(), 6);
+
+    // uN of node 2, then uA of node 4 toward node 3. As srv6_sid_list()
+    // gives it, the uA is not preceded by a uN of node 4, its uN part
+    // already routes to node 4.
+    let list = sids(&["fcbb:bb00:2::", "fcbb:bb00:4:e003::"]);
+    assert_eq!(
+        usid_compress(&list, &format),
+        sids(&["fcbb:bb00:2:4:e003::"])
+    );
+
+    assert!(usid_compress(&[], &format).is_empty());
+}
+
+#[test]
+pub fn usid_container_full() {
+    let format = UsidFormat::F3216;
+
+    let list = sids(&[
+        "fcbb:bb00:1::",
+        "fcbb:bb00:2::",
+        "fcbb:bb00:3::",
+        "fcbb:bb00:4::",
+        "fcbb:bb00:5::",
+        "fcbb:bb00:6::",
+        "fcbb:bb00:7::",
+    ]);
+    assert_eq!(
+        usid_compress(&list, &format),
+        sids(&["fcbb:bb00:1:2:3:4:5:6", "fcbb:bb00:7::"])
+    );
+
+    // A container splits across the boundary.
+    let list = sids(&["fcbb:bb00:1:2:3:4::", "fcbb:bb00:5:6:7::"]);
+    assert_eq!(
+        usid_compress(&list, &format),
+        sids(&["fcbb:bb00:1:2:3:4:5:6", "fcbb:bb00:7::"])
+    );
+}
+
+#[test]
+pub fn usid_block_boundary() {
+    let format = UsidFormat::F3216;
+
+    // A different block starts a new container.
+    let list = sids(&["fcbb:bb00:1::", "fcbb:bb01:2::", "fcbb:bb01:3::"]);
+    assert_eq!(
+        usid_compress(&list, &format),
+        sids(&["fcbb:bb00:1::", "fcbb:bb01:2:3::"])
+    );
+
+    // SIDs not in uSID form are kept as is and end the container.
+    let list = sids(&[
+        "fcbb:bb00:1::",
+        "fc00:0:4::1",
+        "fcbb:bb00:2::",
+        "fcbb:bb00::",
+        "fcbb:bb00:3::",
+    ]);
+    assert_eq!(
+        usid_compress(&list, &format),
+        sids(&[
+            "fcbb:bb00:1::",
+            "fc00:0:4::1",
+            "fcbb:bb00:2::",
+            "fcbb:bb00::",
+            "fcbb:bb00:3::",
+        ])
+    );
+
+    let format = UsidFormat::new(48, 16);
+    assert_eq!(format.capacity(), 5);
+    let list = sids(&["fcbb:bb00:0:1::", "fcbb:bb00:0:2::"]);
+    assert_eq!(usid_compress(&list, &format), sids(&["fcbb:bb00:0:1:2::"]));
+}